@@ -0,0 +1,57 @@
+use std::hint::black_box;
+
+use backgammon::{backgammon::{Board, Dice, Player}, engine::find_best_move, game};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+/// Rolls covering a regular roll, a small and a large double.
+const DICE: [(u8, u8); 4] = [(3, 1), (6, 5), (2, 2), (6, 6)];
+
+/// Same position as [`game::Board::bench`]: both players have blots in contact and White is on the bar.
+fn midgame() -> Board {
+    let points = [1, -2, -2, 1, 1, 0, 1, -1, 0, 0, 0, -2, -1, -1, 0, 0, 0, 0, 6, -1, -1, -4, 2, 2];
+    Board::from_points(points, 1, 0, 0, 0, Player::White).unwrap()
+}
+
+fn generate_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_moves");
+    for (name, board) in [("opening", Board::new()), ("midgame", midgame())] {
+        for (die1, die2) in DICE {
+            let dice = Dice::from_numbers(die1, die2);
+            group.bench_with_input(BenchmarkId::new(name, format!("{}-{}", die1, die2)), &dice, |b, &dice| {
+                b.iter(|| black_box(&board).generate_moves(dice))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn best_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_best_move");
+    group.sample_size(10);
+    // Black's reply to the opening, White's first move would be answered by the opening book.
+    let mut reply = game::Board::new();
+    reply.switch_player();
+    for (name, board) in [("opening reply", reply), ("midgame", game::Board::bench())] {
+        group.bench_function(BenchmarkId::new(name, "depth 2"), |b| {
+            b.iter(|| find_best_move(black_box(&board), game::Dice::new(5, 3), 2))
+        });
+    }
+    group.finish();
+}
+
+fn switch_player(c: &mut Criterion) {
+    let mut group = c.benchmark_group("switch_player");
+    for (name, board) in [("opening", Board::new()), ("midgame", midgame())] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut board = black_box(board);
+                board.switch_player();
+                board
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generate_moves, best_move, switch_player);
+criterion_main!(benches);
@@ -0,0 +1,227 @@
+//! One-sided bear-off database.
+//!
+//! For every distribution of up to 15 checkers across the six home board points the
+//! database stores the expected number of rolls needed to bear off all checkers, and the
+//! probability of needing exactly `n` rolls, both for the play minimizing the expected rolls.
+//! Positions are indexed by their rank among all distributions, see [`index`].
+//! A position is solved on its first query, together with the positions it can reach,
+//! so small endgames never pay for solving the whole table.
+
+use std::sync::{LazyLock, OnceLock};
+
+const POINTS: usize = 6;
+const MAX_CHECKERS: usize = 15;
+/// Upper bound for the number of rolls needed to bear off 15 checkers from the home board.
+pub const MAX_ROLLS: usize = 40;
+/// Number of distributions of up to 15 checkers across the six home board points.
+pub const POSITIONS: usize = binomial(MAX_CHECKERS + POINTS, POINTS);
+
+/// Checkers on the home board points, index 0 is the ace point and index 5 the six point.
+pub type HomeBoard = [u8; POINTS];
+
+pub struct BearoffDatabase {
+    entries: Vec<OnceLock<Solved>>,
+}
+
+struct Solved {
+    expected_rolls: f32,
+    roll_distribution: [f32; MAX_ROLLS],
+}
+
+static DATABASE: LazyLock<BearoffDatabase> = LazyLock::new(BearoffDatabase::new);
+
+/// Returns the database. Positions are solved when they are first queried.
+pub fn database() -> &'static BearoffDatabase {
+    &DATABASE
+}
+
+/// Compact index of a home board distribution in `0..POSITIONS`.
+pub fn index(home_board: &HomeBoard) -> usize {
+    let mut remaining = MAX_CHECKERS;
+    let mut rank = 0;
+    for (point, &checkers) in home_board.iter().enumerate().rev() {
+        // Count all distributions with fewer checkers on this point and the same higher points.
+        for fewer in 0..checkers as usize {
+            rank += binomial(remaining - fewer + point, point);
+        }
+        remaining -= checkers as usize;
+    }
+    rank
+}
+
+impl BearoffDatabase {
+    fn new() -> Self {
+        BearoffDatabase { entries: (0..POSITIONS).map(|_| OnceLock::new()).collect() }
+    }
+
+    /// Expected number of rolls needed to bear off all checkers.
+    pub fn expected_rolls(&self, home_board: &HomeBoard) -> f32 {
+        self.solved(home_board).expected_rolls
+    }
+
+    /// Probability of needing exactly `n` rolls to bear off all checkers, indexed by `n`.
+    pub fn roll_distribution(&self, home_board: &HomeBoard) -> &[f32; MAX_ROLLS] {
+        &self.solved(home_board).roll_distribution
+    }
+
+    /// Probability that the player on roll bears off all checkers before the opponent.
+    pub fn win_probability(&self, on_roll: &HomeBoard, opponent: &HomeBoard) -> f32 {
+        let on_roll = self.roll_distribution(on_roll);
+        let opponent = self.roll_distribution(opponent);
+        // The player on roll wins by finishing in `n` rolls if the opponent needs at least `n`.
+        let mut opponent_needs_at_least = 1.0;
+        let mut probability = 0.0;
+        for n in 0..MAX_ROLLS {
+            probability += on_roll[n] * opponent_needs_at_least;
+            opponent_needs_at_least -= opponent[n];
+        }
+        probability
+    }
+
+    /// Every play lowers the pip count, so solving a position only recurses into positions
+    /// with fewer pips and never waits for itself, also when queried from several threads.
+    fn solved(&self, home_board: &HomeBoard) -> &Solved {
+        self.entries[index(home_board)].get_or_init(|| self.solve(home_board))
+    }
+
+    fn solve(&self, home_board: &HomeBoard) -> Solved {
+        let mut roll_distribution = [0.0; MAX_ROLLS];
+        if home_board.iter().all(|&c| c == 0) {
+            roll_distribution[0] = 1.0;
+            return Solved { expected_rolls: 0.0, roll_distribution };
+        }
+        // Accumulate in 36ths so that forced positions sum up exactly.
+        let mut expected_rolls = 0.0;
+        for die1 in 1..=6u8 {
+            for die2 in die1..=6u8 {
+                let weight = if die1 == die2 { 1.0 } else { 2.0 };
+                let best = if die1 == die2 {
+                    self.best_play(home_board, &[die1; 4])
+                } else {
+                    let a = self.best_play(home_board, &[die1, die2]);
+                    let b = self.best_play(home_board, &[die2, die1]);
+                    if b.expected_rolls < a.expected_rolls { b } else { a }
+                };
+                expected_rolls += weight * best.expected_rolls;
+                for (next, &previous) in roll_distribution[1..].iter_mut().zip(&best.roll_distribution) {
+                    *next += weight * previous;
+                }
+            }
+        }
+        roll_distribution.iter_mut().for_each(|p| *p /= 36.0);
+        Solved { expected_rolls: 1.0 + expected_rolls / 36.0, roll_distribution }
+    }
+
+    /// The position with the fewest expected rolls reachable by playing `dice` in order.
+    fn best_play(&self, home_board: &HomeBoard, dice: &[u8]) -> &Solved {
+        let mut reachable = vec![*home_board];
+        for &die in dice {
+            let mut next = Vec::new();
+            for home_board in &reachable {
+                let plays = single_die_plays(home_board, die);
+                if plays.is_empty() {
+                    next.push(*home_board);
+                } else {
+                    next.extend(plays);
+                }
+            }
+            // Playing the dice of a double in a different order often reaches the same position.
+            next.sort_unstable();
+            next.dedup();
+            reachable = next;
+        }
+        reachable.iter()
+            .map(|home_board| self.solved(home_board))
+            .min_by(|a, b| a.expected_rolls.total_cmp(&b.expected_rolls))
+            .expect("There is at least one play")
+    }
+}
+
+/// All positions reachable by playing a single die, following the bear-off rules.
+fn single_die_plays(home_board: &HomeBoard, die: u8) -> Vec<HomeBoard> {
+    let die = die as usize;
+    let highest = home_board.iter().rposition(|&c| c > 0);
+    let mut plays = Vec::new();
+    for point in 0..POINTS {
+        if home_board[point] == 0 {
+            continue;
+        }
+        let distance = point + 1;
+        let mut next = *home_board;
+        next[point] -= 1;
+        if distance > die {
+            next[point - die] += 1;
+        } else if distance < die && Some(point) != highest {
+            // A larger die only bears off from the highest occupied point.
+            continue;
+        }
+        plays.push(next);
+    }
+    plays
+}
+
+const fn binomial(n: usize, k: usize) -> usize {
+    let mut result = 1;
+    let mut i = 0;
+    while i < k {
+        result = result * (n - i) / (i + 1);
+        i += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enumerate(home_board: &mut HomeBoard, point: usize, remaining: u8, positions: &mut Vec<HomeBoard>) {
+        if point == POINTS {
+            positions.push(*home_board);
+            return;
+        }
+        for checkers in 0..=remaining {
+            home_board[point] = checkers;
+            enumerate(home_board, point + 1, remaining - checkers, positions);
+        }
+        home_board[point] = 0;
+    }
+
+    #[test]
+    fn test_index_is_compact() {
+        let mut positions = Vec::new();
+        enumerate(&mut [0; POINTS], 0, MAX_CHECKERS as u8, &mut positions);
+        assert_eq!(positions.len(), POSITIONS);
+        let mut seen = vec![false; POSITIONS];
+        for home_board in positions {
+            let rank = index(&home_board);
+            assert!(!seen[rank]);
+            seen[rank] = true;
+        }
+        assert_eq!(index(&[0; POINTS]), 0);
+    }
+
+    #[test]
+    fn test_known_values() {
+        let db = database();
+        // Two checkers on the ace point are always borne off with one roll.
+        assert_eq!(db.expected_rolls(&[2, 0, 0, 0, 0, 0]), 1.0);
+        assert_eq!(db.roll_distribution(&[2, 0, 0, 0, 0, 0])[1], 1.0);
+        // A single checker on the six point needs a second roll with 9 of 36 rolls.
+        let distribution = db.roll_distribution(&[0, 0, 0, 0, 0, 1]);
+        assert!((distribution[1] - 27.0 / 36.0).abs() < 1e-6);
+        assert!((db.expected_rolls(&[0, 0, 0, 0, 0, 1]) - 1.25).abs() < 1e-6);
+        assert!(db.expected_rolls(&[15, 0, 0, 0, 0, 0]) > db.expected_rolls(&[14, 0, 0, 0, 0, 0]));
+        // Positions needing many rolls fit into the distribution. Stacking all 15 checkers on
+        // the six point would solve the whole table, which takes too long for a debug build.
+        let total = db.roll_distribution(&[0, 0, 0, 0, 0, 8]).iter().sum::<f32>();
+        assert!((total - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_win_probability() {
+        let db = database();
+        assert_eq!(db.win_probability(&[1, 0, 0, 0, 0, 0], &[1, 0, 0, 0, 0, 0]), 1.0);
+        let p = db.win_probability(&[0, 0, 0, 0, 0, 1], &[1, 0, 0, 0, 0, 0]);
+        assert!((p - 27.0 / 36.0).abs() < 1e-6);
+    }
+}
@@ -1,4 +1,86 @@
-use crate::{backgammon::{Dice, HalfMove, Move, Player, PositionCompressed}, misc::TinyVec};
+use core::{fmt::Display, hash::{Hash, Hasher}};
+
+#[cfg(feature = "std")]
+use hashbrown::HashSet;
+#[cfg(feature = "std")]
+use rand::Rng;
+
+use crate::{backgammon::{zobrist::ZOBRIST_KEYS, Dice, GameOutcome, GamePhase, HalfMove, Move, Player, Position, PositionCompressed}, misc::TinyVec};
+#[cfg(feature = "std")]
+use crate::backgammon::bearoff;
+
+/// Error returned by [`Board::make_move`] when a move cannot be played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The move is not one of the legal moves for the given dice.
+    NotInLegalSet,
+    /// A half move covers a distance that none of the available dice can produce.
+    WrongDice,
+    /// The game has already ended.
+    GameAlreadyOver,
+    /// No dice have been rolled for the move.
+    DiceNotRolled,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MoveError::NotInLegalSet => write!(f, "move is not legal for the given dice"),
+            MoveError::WrongDice => write!(f, "move does not match the given dice"),
+            MoveError::GameAlreadyOver => write!(f, "game is already over"),
+            MoveError::DiceNotRolled => write!(f, "dice have not been rolled"),
+        }
+    }
+}
+
+impl core::error::Error for MoveError {}
+
+/// Error returned by [`Board::from_points`] and [`Board::validate`] for positions that cannot occur in a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+    /// A player does not have exactly 15 checkers on the points, the bar and borne off.
+    WrongCheckerCount { player: Player, count: u32 },
+    /// More than 15 checkers are placed on a single point.
+    TooManyCheckersOnPoint { index: u8, count: i8 },
+    /// More than 15 checkers are placed on a bar or borne off, e.g. `field` is `"active bar"`.
+    TooManyCheckersOffBoard { field: &'static str, count: u8 },
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PositionError::WrongCheckerCount { player, count } => write!(f, "{:?} has {} checkers instead of 15", player, count),
+            PositionError::TooManyCheckersOnPoint { index, count } => write!(f, "point {} holds {} checkers", index, count),
+            PositionError::TooManyCheckersOffBoard { field, count } => write!(f, "{} holds {} checkers", field, count),
+        }
+    }
+}
+
+impl core::error::Error for PositionError {}
+
+/// Error returned by [`Board::from_diagram`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseDiagramError {
+    /// A line is not of the form `"<point>: <W|B><count>"` or `"active: <White|Black>"`,
+    /// or places checkers on a point that is already occupied.
+    Malformed(String),
+    /// The checkers do not form a valid position, e.g. a player does not have 15 checkers.
+    InvalidPosition(PositionError),
+}
+
+#[cfg(feature = "std")]
+impl Display for ParseDiagramError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseDiagramError::Malformed(line) => write!(f, "malformed diagram line \"{}\", expected e.g. \"24: W2\"", line),
+            ParseDiagramError::InvalidPosition(error) => write!(f, "invalid position: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for ParseDiagramError {}
 
 /// Representation of a Backgammon board using 128 bits.
 /// The board is spit into two [`u64`] values. The 4 least significant bits
@@ -7,7 +89,12 @@ use crate::{backgammon::{Dice, HalfMove, Move, Player, PositionCompressed}, misc
 /// The board always represents the position from the perspective of the active player.
 /// The memory layout aims to be compact while allowing fast access for move generation and fast inversion 
 /// of the board.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Equality and hashing compare the logical position: an empty point compares equal
+/// regardless of the sign bit left behind by the last checker.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "SerializedBoard", try_from = "SerializedBoard"))]
 pub struct Board {
     board: [u64; 2],
     home: u8,
@@ -30,6 +117,24 @@ impl Board {
         }
     }
 
+    /// Creates the starting position with `active` to move. The opening position is symmetric,
+    /// so seen from the player to move it is the same for both players.
+    pub fn new_with_player(active: Player) -> Self {
+        Board { active_player: active, ..Self::new() }
+    }
+
+    /// Creates a board with the starting position of the Nackgammon variant. Compared to
+    /// [`Self::new`] each side moves one checker from the mid point and one from the six point
+    /// back to the 23 point, so the game starts with four back checkers.
+    pub fn nackgammon() -> Self {
+        let mut points = [0; 24];
+        for (point, checkers) in [(24, 2), (23, 2), (13, 4), (8, 3), (6, 4)] {
+            points[24 - point] = checkers;
+            points[point - 1] = -checkers;
+        }
+        Board::from_points(points, 0, 0, 0, 0, Player::White).expect("The Nackgammon position is valid")
+    }
+
     /// Creates an empty board with no checkers on it.
     pub fn empty() -> Self {
         Board {
@@ -39,6 +144,123 @@ impl Board {
         }
     }
 
+    /// Creates a position from the points seen from the active player, positive counts are the
+    /// active player's checkers. Each player needs exactly 15 checkers in total.
+    pub fn from_points(points: [i8; 24], active_bar: u8, passive_bar: u8, active_home: u8, passive_home: u8, active_player: Player) -> Result<Board, PositionError> {
+        if let Some((index, &count)) = points.iter().enumerate().find(|(_, count)| count.unsigned_abs() > 15) {
+            return Err(PositionError::TooManyCheckersOnPoint { index: index as u8, count });
+        }
+        let off_board = [("active bar", active_bar), ("passive bar", passive_bar), ("active home", active_home), ("passive home", passive_home)];
+        if let Some(&(field, count)) = off_board.iter().find(|(_, count)| *count > 15) {
+            return Err(PositionError::TooManyCheckersOffBoard { field, count });
+        }
+        let active_count = points.iter().filter(|&&c| c > 0).map(|&c| c as u32).sum::<u32>() + active_bar as u32 + active_home as u32;
+        if active_count != 15 {
+            return Err(PositionError::WrongCheckerCount { player: active_player, count: active_count });
+        }
+        let passive_count = points.iter().filter(|&&c| c < 0).map(|&c| c.unsigned_abs() as u32).sum::<u32>() + passive_bar as u32 + passive_home as u32;
+        if passive_count != 15 {
+            return Err(PositionError::WrongCheckerCount { player: active_player.opposite(), count: passive_count });
+        }
+
+        let mut board = Board::empty();
+        for (index, &checkers) in points.iter().enumerate() {
+            board.set_checkers_on_position(index as u8, checkers);
+        }
+        board.set_active_bar(active_bar);
+        board.set_passive_bar(passive_bar);
+        board.set_active_home(active_home);
+        board.set_passive_home(passive_home);
+        board.active_player = active_player;
+        Ok(board)
+    }
+
+    /// Parses a position written one entry per line, e.g. `"24: W2"`, `"13: B5"`, `"bar: W1"`, `"off: B3"`
+    /// and `"active: Black"`. Like in move notation every player counts the points from their own side,
+    /// so `"24: W2"` and `"24: B2"` are the back checkers of both players in the opening. White is to move
+    /// unless an `active` line says otherwise, blank lines are ignored.
+    #[cfg(feature = "std")]
+    pub fn from_diagram(s: &str) -> Result<Board, ParseDiagramError> {
+        // Points numbered from each player's side, then bar and borne off, indexed by White and Black.
+        let mut checkers = [[0u8; 26]; 2];
+        let mut active_player = Player::White;
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let malformed = || ParseDiagramError::Malformed(line.to_string());
+            let (key, value) = line.split_once(':').ok_or_else(malformed)?;
+            let value = value.trim();
+            if key.trim() == "active" {
+                active_player = match value {
+                    "White" | "W" => Player::White,
+                    "Black" | "B" => Player::Black,
+                    _ => return Err(malformed()),
+                };
+                continue;
+            }
+
+            let slot = match key.trim() {
+                "bar" => 24,
+                "off" => 25,
+                point => match point.parse::<usize>() {
+                    Ok(point @ 1..=24) => point - 1,
+                    _ => return Err(malformed()),
+                },
+            };
+            let player = match value.get(..1) {
+                Some("W") => Player::White,
+                Some("B") => Player::Black,
+                _ => return Err(malformed()),
+            };
+            let count = value[1..].parse::<u8>().map_err(|_| malformed())?;
+            // The same point seen from the other player's side.
+            let opposite = if slot < 24 { checkers[player.opposite() as usize][23 - slot] } else { 0 };
+            if checkers[player as usize][slot] != 0 || opposite != 0 || count > 15 {
+                return Err(malformed());
+            }
+            checkers[player as usize][slot] = count;
+        }
+
+        let [active, passive] = match active_player {
+            Player::White => checkers,
+            Player::Black => [checkers[1], checkers[0]],
+        };
+        let mut points = [0i8; 24];
+        for point in 1..=24 {
+            points[24 - point] += active[point - 1] as i8;
+            points[point - 1] -= passive[point - 1] as i8;
+        }
+        Board::from_points(points, active[24], passive[24], active[25], passive[25], active_player)
+            .map_err(ParseDiagramError::InvalidPosition)
+    }
+
+    /// Return the player whose turn it is.
+    pub fn active_player(&self) -> Player {
+        self.active_player
+    }
+
+    /// Changes whose turn it is without changing the perspective of the points, bars and homes.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_active_player(&mut self, player: Player) {
+        self.active_player = player;
+    }
+
+    /// Checks that both players have exactly 15 checkers on the points, the bar and borne off.
+    /// Every count is stored in 4 bits, so putting more than 15 checkers on a point with the
+    /// setters spills into the sign bit and shows up as a wrong checker count.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        let points: [i8; 24] = core::array::from_fn(|i| self.get_checkers_on_position(i as u8));
+        let active_count = points.iter().filter(|&&c| c > 0).map(|&c| c as u32).sum::<u32>()
+            + self.get_active_bar() as u32 + self.get_active_home() as u32;
+        if active_count != 15 {
+            return Err(PositionError::WrongCheckerCount { player: self.active_player, count: active_count });
+        }
+        let passive_count = points.iter().filter(|&&c| c < 0).map(|&c| c.unsigned_abs() as u32).sum::<u32>()
+            + self.get_passive_bar() as u32 + self.get_passive_home() as u32;
+        if passive_count != 15 {
+            return Err(PositionError::WrongCheckerCount { player: self.active_player.opposite(), count: passive_count });
+        }
+        Ok(())
+    }
+
     /// Return the number of checkers on the bar for the active player.
     pub fn get_active_bar(&self) -> u8 {
         (self.board[0] & 0x000000000000000F) as u8
@@ -75,6 +297,152 @@ impl Board {
         self.home >> 4
     }
 
+    pub fn set_passive_home(&mut self, value: u8) {
+        self.home = (self.home & 0x0F) | ((value & 0xF) << 4);
+    }
+
+    /// Returns the outcome of the game. A win is a gammon if the loser has not borne off
+    /// any checkers, and a backgammon if the loser additionally still has a checker on the
+    /// bar or in the winner's home board.
+    pub fn outcome(&self) -> GameOutcome {
+        let passive_in_active_home = || (18..24).any(|i| self.get_checkers_on_position(i) < 0);
+        let active_in_passive_home = || (0..6).any(|i| self.get_checkers_on_position(i) > 0);
+
+        let active = self.active_player;
+        let passive = self.active_player.opposite();
+        match (self.get_active_home(), self.get_passive_home()) {
+            (15, 0) if self.get_passive_bar() > 0 || passive_in_active_home() => GameOutcome::Backgammon(active),
+            (15, 0) => GameOutcome::Gammon(active),
+            (15, _) => GameOutcome::Win(active),
+            (0, 15) if self.get_active_bar() > 0 || active_in_passive_home() => GameOutcome::Backgammon(passive),
+            (0, 15) => GameOutcome::Gammon(passive),
+            (_, 15) => GameOutcome::Win(passive),
+            _ => GameOutcome::Ongoing,
+        }
+    }
+
+    /// Renders the board as ASCII art, always from White's perspective.
+    /// Positive numbers are White checkers, negative numbers are Black checkers.
+    #[cfg(feature = "std")]
+    pub fn to_fancy_string(&self) -> String {
+        let board = self.from_whites_perspective();
+        let p = |index: u8| board.get_checkers_on_position(index);
+        format!(
+"12  11  10   9   8   7  | W  |   6   5   4   3   2   1
+{:2}  {:2}  {:2}  {:2}  {:2}  {:2}  | {:2} |  {:2}  {:2}  {:2}  {:2}  {:2}  {:2}
+==========================================================
+{:2}  {:2}  {:2}  {:2}  {:2}  {:2}  | {:2} |   {:2}  {:2}  {:2}  {:2}  {:2}  {:2}
+13  14  15  16  17  18  | B  |   19  20  21  22  23  24
+Borne off: W {} | B {}",
+            p(11), p(10), p(9), p(8), p(7), p(6),
+            board.get_active_bar(),
+            p(5), p(4), p(3), p(2), p(1), p(0),
+            p(12), p(13), p(14), p(15), p(16), p(17),
+            board.get_passive_bar(),
+            p(18), p(19), p(20), p(21), p(22), p(23),
+            board.get_active_home(), board.get_passive_home())
+    }
+
+    /// Renders the board from White's perspective like [`Self::to_fancy_string`], with a box-drawing
+    /// frame, `●` for White's and `○` for Black's checkers. As in the GUI, at most 5 checkers are
+    /// stacked on a point and the rest is shown as a count. `color` adds ANSI colors to the checkers.
+    #[cfg(feature = "std")]
+    pub fn pretty_unicode(&self, color: bool) -> String {
+        const STACK_HEIGHT: usize = 5;
+        const TOP: ([u8; 6], [u8; 6]) = ([11, 10, 9, 8, 7, 6], [5, 4, 3, 2, 1, 0]);
+        const BOTTOM: ([u8; 6], [u8; 6]) = ([12, 13, 14, 15, 16, 17], [18, 19, 20, 21, 22, 23]);
+
+        let board = self.from_whites_perspective();
+        let glyph = |checkers: i8| match (checkers > 0, color) {
+            (true, false) => "●",
+            (false, false) => "○",
+            (true, true) => "\x1b[97m●\x1b[0m",
+            (false, true) => "\x1b[91m○\x1b[0m",
+        };
+        // One cell of a stack of `checkers`, positive for White, with `row` counted from the edge of the board.
+        let cell = |checkers: i8, row: usize| {
+            let count = checkers.unsigned_abs() as usize;
+            if row < count.min(STACK_HEIGHT) {
+                format!(" {}  ", glyph(checkers))
+            } else if row == STACK_HEIGHT && count > STACK_HEIGHT {
+                format!("{:^4}", format!("+{}", count - STACK_HEIGHT))
+            } else {
+                "    ".to_string()
+            }
+        };
+        let labels = |indices: [u8; 6]| indices.iter().map(|&i| format!("{:^4}", i + 1)).collect::<String>();
+        let cells = |indices: [u8; 6], row: usize| indices.iter().map(|&i| cell(board.get_checkers_on_position(i), row)).collect::<String>();
+        // The rows of one half, the checkers of the top half are stacked downwards and of the bottom half upwards.
+        let half = |(left, right): ([u8; 6], [u8; 6]), bar: i8, top: bool| {
+            (0..=STACK_HEIGHT)
+                .map(|line| if top { line } else { STACK_HEIGHT - line })
+                .map(|row| format!("│{}│{}│{}│\n", cells(left, row), cell(bar, row), cells(right, row)))
+                .collect::<String>()
+        };
+
+        let mut out = String::from("┌────────────────────────┬────┬────────────────────────┐\n");
+        out += &format!("│{}│ W  │{}│\n", labels(TOP.0), labels(TOP.1));
+        out += &half(TOP, board.get_active_bar() as i8, true);
+        out += "├────────────────────────┼────┼────────────────────────┤\n";
+        out += &half(BOTTOM, -(board.get_passive_bar() as i8), false);
+        out += &format!("│{}│ B  │{}│\n", labels(BOTTOM.0), labels(BOTTOM.1));
+        out += "└────────────────────────┴────┴────────────────────────┘\n";
+        out += &format!("Borne off: W {} | B {}", board.get_active_home(), board.get_passive_home());
+        out
+    }
+
+    /// Zobrist hash of the logical board state, suitable as a transposition table key.
+    /// Boards that represent the same position hash equally regardless of how they were reached.
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for index in 0..24 {
+            let checkers = self.get_checkers_on_position(index);
+            if checkers != 0 {
+                let sign = (checkers < 0) as usize;
+                hash ^= ZOBRIST_KEYS.points[index as usize][sign][checkers.unsigned_abs() as usize];
+            }
+        }
+        hash ^= ZOBRIST_KEYS.bars[0][self.get_active_bar() as usize];
+        hash ^= ZOBRIST_KEYS.bars[1][self.get_passive_bar() as usize];
+        hash ^= ZOBRIST_KEYS.homes[0][self.get_active_home() as usize];
+        hash ^= ZOBRIST_KEYS.homes[1][self.get_passive_home() as usize];
+        if self.active_player == Player::Black {
+            hash ^= ZOBRIST_KEYS.black_to_move;
+        }
+        hash
+    }
+
+    /// Sum of the point numbers (index + 1) of all blots hit by `m`, used for move ordering.
+    pub fn captured_value(&self, m: &Move) -> u8 {
+        let mut sum = 0;
+        for half_move in m.iter() {
+            match Position::from(*half_move.to()) {
+                Position::Board(n) if self.get_checkers_on_position(n) == -1 => sum += n + 1,
+                _ => (),
+            }
+        }
+
+        sum
+    }
+
+    /// Indices of the points where `m` hits a blot of the passive player, in the order of its
+    /// half moves. The move is replayed on a copy, so a checker hitting on its way is found too.
+    pub fn hits(&self, m: &Move) -> TinyVec<u8, 4> {
+        let mut board = *self;
+        let mut hits = TinyVec::new();
+        for half_move in m.iter() {
+            if let Some(index) = board.apply_half_move(half_move) {
+                hits.push(index);
+            }
+        }
+        hits
+    }
+
+    /// Returns true if `m` hits at least one blot, see [`Self::hits`].
+    pub fn is_hitting(&self, m: &Move) -> bool {
+        !self.hits(m).is_empty()
+    }
+
     pub fn get_player_on_position(&self, index: u8) -> Option<Player> {
         let index_offset = Self::index_offset(index);
         let board_index = (index / 12) as usize;
@@ -145,6 +513,39 @@ impl Board {
 
     const INVERT_SIGN_MASK: u64 = 0b1000010000100001000010000100001000010000100001000010000100000000;
 
+    /// The sign bits of the empty points in a board word.
+    fn empty_point_sign_bits(word: u64) -> u64 {
+        const LOWEST_COUNT_BITS: u64 = Board::INVERT_SIGN_MASK >> 4;
+        const COUNT_MASK: u64 = LOWEST_COUNT_BITS * 0xF;
+        let counts = word & COUNT_MASK;
+        let occupied = (counts | counts >> 1 | counts >> 2 | counts >> 3) & LOWEST_COUNT_BITS;
+        Self::INVERT_SIGN_MASK & !(occupied << 4)
+    }
+
+    /// The board words with the sign bits of empty points cleared.
+    fn normalized_board(&self) -> [u64; 2] {
+        self.board.map(|word| word & !Self::empty_point_sign_bits(word))
+    }
+
+    /// A 128 bit key of the position seen from White, including whose turn it is.
+    ///
+    /// Two valid positions (see [`Self::validate`]) get the same key exactly if they have the same
+    /// checkers on the points and bars, seen from White, and the same player to move. Borne off
+    /// checkers are not stored, since they follow from the other checkers. Black to move is marked by setting the sign bits of the empty points,
+    /// or the highest bit of White's bar count if no point is empty, which then holds at most 6 checkers.
+    pub fn canonical_key(&self) -> u128 {
+        let mut words = self.from_whites_perspective().normalized_board();
+        if self.active_player == Player::Black {
+            let empty = words.map(Self::empty_point_sign_bits);
+            if empty == [0, 0] {
+                words[0] |= 0b1000;
+            } else {
+                words = [words[0] | empty[0], words[1] | empty[1]];
+            }
+        }
+        (words[1] as u128) << 64 | words[0] as u128
+    }
+
     pub fn switch_player(&mut self) {
         self.board[0] ^= self.board[1];
         self.board[1] ^= self.board[0];
@@ -155,14 +556,226 @@ impl Board {
         self.home = (self.home << 4) | self.home >> 4; 
     }
 
+    /// Passes the turn to the opponent without moving a checker. This is the only legal
+    /// action when [`Self::has_any_move`] is false for the rolled dice.
+    pub fn pass_turn(&mut self) {
+        self.switch_player();
+    }
+
+    /// Returns the board seen from White: positive counts, the active bar and the active home
+    /// belong to White. Unlike [`Self::switch_player`], whose turn it is does not change.
+    pub fn from_whites_perspective(&self) -> Board {
+        match self.active_player {
+            Player::White => *self,
+            Player::Black => self.mirrored(),
+        }
+    }
+
+    /// Returns the opponent's view of the board: the points, bars and homes are swapped
+    /// like in [`Self::switch_player`], but the active player stays the same.
+    pub fn mirrored(&self) -> Board {
+        let mut board = *self;
+        board.switch_player();
+        board.active_player = self.active_player;
+        board
+    }
+
+    /// Remaps the points of `m` to the view of [`Self::mirrored`]: `Board(i)` becomes `Board(23 - i)`,
+    /// the bar and home stay the bar and home. Keeps move lists in line with a flipped board,
+    /// e.g. the moves of Black rendered with [`Self::from_whites_perspective`].
+    pub fn mirror_move(m: &Move) -> Move {
+        let mirror = |position: PositionCompressed| match Position::from(position) {
+            Position::Board(index) => Position::Board(23 - index).into(),
+            _ => position,
+        };
+        let mut mirrored = Move::new();
+        for half_move in m.iter() {
+            mirrored.add_half_move(HalfMove::from_compressed(mirror(*half_move.from()), mirror(*half_move.to())));
+        }
+        mirrored
+    }
+
+    /// Iterates over all points holding checkers as `(index, checkers)`,
+    /// with positive counts for the active player.
+    pub fn occupied(&self) -> impl Iterator<Item = (u8, i8)> {
+        (0..24)
+            .map(move |index| (index, self.get_checkers_on_position(index)))
+            .filter(|&(_, checkers)| checkers != 0)
+    }
+
+    /// Where the checkers of `player` are, as `(position, count)`: the bar, the points in the
+    /// order `player` moves through them and the borne off checkers, leaving out empty ones.
+    /// Point indices are seen from `player`, independent of whose turn it is.
+    #[cfg(feature = "std")]
+    pub fn checker_positions(&self, player: Player) -> Vec<(Position, u8)> {
+        let (bar, home) = if player == self.active_player {
+            (self.get_active_bar(), self.get_active_home())
+        } else {
+            (self.get_passive_bar(), self.get_passive_home())
+        };
+        let mut positions = Vec::new();
+        if bar > 0 {
+            positions.push((Position::Bar, bar));
+        }
+        for index in 0..24 {
+            let checkers = if player == self.active_player {
+                self.get_checkers_on_position(index)
+            } else {
+                -self.get_checkers_on_position(23 - index)
+            };
+            if checkers > 0 {
+                positions.push((Position::Board(index), checkers as u8));
+            }
+        }
+        if home > 0 {
+            positions.push((Position::Home, home));
+        }
+        positions
+    }
+
+    /// Total number of checkers of `player` on the board, on the bar and borne off.
+    /// Always 15 in a valid game.
+    pub fn checker_count(&self, player: Player) -> u8 {
+        if player == self.active_player {
+            let on_board: u8 = self.occupied().filter(|&(_, c)| c > 0).map(|(_, c)| c as u8).sum();
+            on_board + self.get_active_bar() + self.get_active_home()
+        } else {
+            let on_board: u8 = self.occupied().filter(|&(_, c)| c < 0).map(|(_, c)| c.unsigned_abs()).sum();
+            on_board + self.get_passive_bar() + self.get_passive_home()
+        }
+    }
+
+    /// Number of checkers `player` has borne off, independent of whose turn it is.
+    pub fn borne_off(&self, player: Player) -> u8 {
+        if player == self.active_player {
+            self.get_active_home()
+        } else {
+            self.get_passive_home()
+        }
+    }
+
+    /// Fraction of the 15 checkers `player` has borne off, from 0.0 to 1.0.
+    pub fn bear_off_progress(&self, player: Player) -> f32 {
+        self.borne_off(player) as f32 / 15.0
+    }
+
+    /// Length of the longest run of consecutive points held by `player` with at least two checkers.
+    pub fn longest_prime(&self, player: Player) -> u8 {
+        let mut longest = 0;
+        let mut current = 0;
+        for index in 0..24 {
+            if self.holds_point(player, index) {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        longest
+    }
+
+    /// Returns true if `player` holds all six points of their home board
+    /// while the opponent has a checker on the bar.
+    pub fn is_closed_out(&self, player: Player) -> bool {
+        let (home_board, opponent_bar) = if player == self.active_player {
+            (18..24, self.get_passive_bar())
+        } else {
+            (0..6, self.get_active_bar())
+        };
+        opponent_bar > 0 && home_board.into_iter().all(|index| self.holds_point(player, index))
+    }
+
+    /// Number of the active player's entry points, indices 0 to 5, held by the passive player.
+    /// A checker on the bar enters on index `die - 1`.
+    pub fn blocked_entry_points(&self) -> u8 {
+        (0..6).filter(|&index| self.holds_point(self.active_player.opposite(), index)).count() as u8
+    }
+
+    /// Probability that the active player can enter at least one checker from the bar with the
+    /// next roll: one minus the chance that both dice show a blocked entry point.
+    pub fn entry_chance(&self) -> f32 {
+        let blocked = self.blocked_entry_points() as f32 / 6.0;
+        1.0 - blocked * blocked
+    }
+
+    /// Probability that the passive player can land on `index` with their next roll, i.e. hit
+    /// a blot there. Every roll is played out with the legal moves, so entering from the bar
+    /// first and blocked intermediate points of combined routes are respected.
+    #[cfg(feature = "std")]
+    pub fn hit_probability(&self, index: u8) -> f32 {
+        let mut board = *self;
+        board.switch_player();
+        let target = Position::Board(23 - index);
+        // Counting the hitting rolls out of 36 keeps an unavoidable shot at exactly 1.
+        let hits = Dice::ALL.iter()
+            .filter(|dice| {
+                board.generate_moves(**dice).iter()
+                    .any(|m| m.iter().any(|half_move| Position::from(*half_move.to()) == target))
+            })
+            .map(|dice| if dice.is_double() { 1 } else { 2 })
+            .sum::<u32>();
+        hits as f32 / 36.0
+    }
+
+    /// Indices of the points where `player` has exactly one checker.
+    pub fn blots(&self, player: Player) -> impl Iterator<Item = u8> {
+        let sign = if player == self.active_player { 1 } else { -1 };
+        (0..24).filter(move |&i| self.get_checkers_on_position(i) == sign)
+    }
+
+    /// Expected number of pips the active player loses to hits in the opponent's next roll.
+    /// A hit blot at index `i` loses the `i + 1` pips it has travelled. For every roll the
+    /// opponent is assumed to play the move hitting the most pips, so a roll that can hit
+    /// either of two blots, but not both, counts only once.
+    #[cfg(feature = "std")]
+    pub fn total_blot_exposure(&self) -> f32 {
+        let blots: Vec<u8> = self.blots(self.active_player).collect();
+        if blots.is_empty() {
+            return 0.0;
+        }
+        let mut board = *self;
+        board.switch_player();
+        Dice::ALL_WITH_PROBABILITY.iter()
+            .map(|(dice, probability)| {
+                let pips_lost = board.generate_moves(*dice).iter()
+                    .map(|m| {
+                        blots.iter()
+                            .filter(|&&blot| m.iter().any(|half_move| Position::from(*half_move.to()) == Position::Board(23 - blot)))
+                            .map(|&blot| blot as u32 + 1)
+                            .sum::<u32>()
+                    })
+                    .max()
+                    .unwrap_or(0);
+                probability * pips_lost as f32
+            })
+            .sum()
+    }
+
+    fn holds_point(&self, player: Player, index: u8) -> bool {
+        let checkers = self.get_checkers_on_position(index);
+        if player == self.active_player { checkers >= 2 } else { checkers <= -2 }
+    }
+
     pub fn active_home_board(&self) -> impl Iterator<Item = i8> {
         (0..6).map(move |i| self.get_checkers_on_position(18 + i as u8))
     }
 
+    /// Removes moves that consist of the same half moves in a different order, keeping the first
+    /// of each, e.g. for move lists built outside of [`Self::generate_moves`], which never returns
+    /// such duplicates. The order of the remaining moves is preserved.
+    #[cfg(feature = "std")]
+    pub fn dedup_moves(moves: Vec<Move>) -> Vec<Move> {
+        let mut seen = HashSet::with_capacity(moves.len());
+        moves.into_iter().filter(|m| seen.insert(*m)).collect()
+    }
+
+    #[cfg(feature = "std")]
     pub fn generate_moves(&self, dice: Dice) -> Vec<Move> {
+        debug_assert_eq!(self.validate(), Ok(()), "generate_moves called on an invalid position");
         let mut stack: Vec<(Dice, Board, Move)> = vec![(dice, *self, Move::new())];
         let mut next_stack: Vec<(Dice, Board, Move)> = Vec::new();
         
+        let mut seen = HashSet::new();
         let mut best_result_len = 0;
         let mut results = Vec::new();
 
@@ -194,15 +807,317 @@ impl Board {
                 break;
             }
             stack.clear();
+            seen.clear();
             for e in next_stack.drain(..) {
-                if !stack.iter().any(|(_, _, mv)| mv.unordered_equal(&e.2)) {
+                if seen.insert(e.2.unordered_key()) {
                     stack.push(e);
                 }
             }
         }
+        if best_result_len == 1 && let Some(larger) = self.larger_die_half_moves(dice) {
+            results.retain(|m| larger.iter().any(|(half_move, _)| m.iter().next() == Some(half_move)));
+        }
         results
     }
 
+    /// Yields the moves of [`Self::generate_moves`] one at a time, possibly in a different order.
+    /// Only the maximal number of playable dice is determined up front, which usually ends with
+    /// the first move using all dice. After that moves are built depth first, so the first one
+    /// is available without generating the others.
+    #[cfg(feature = "std")]
+    pub fn moves_iter(&self, dice: Dice) -> impl Iterator<Item = Move> {
+        let len = self.max_move_len(dice);
+        MovesIter {
+            stack: vec![(dice, *self, Move::new())],
+            seen: HashSet::new(),
+            len,
+            larger: if len == 1 { self.larger_die_half_moves(dice) } else { None },
+        }
+    }
+
+    /// Largest number of half moves that can be played with `dice`.
+    #[cfg(feature = "std")]
+    fn max_move_len(&self, dice: Dice) -> usize {
+        let all_dice = if dice.is_double() { 4 } else { 2 };
+        let mut stack = vec![(dice, *self, Move::new())];
+        let mut seen = HashSet::new();
+        let mut best = 0;
+        while let Some((dice, board, m)) = stack.pop() {
+            best = best.max(m.len() as usize);
+            if best == all_dice {
+                break;
+            }
+            for &(half_move, remaining_dice) in board.generate_half_moves(dice).iter() {
+                let mut m = m;
+                m.add_half_move(half_move);
+                if seen.insert(m.unordered_key()) {
+                    let mut board = board;
+                    board.make_halfmove_unchecked(&half_move);
+                    stack.push((remaining_dice, board, m));
+                }
+            }
+        }
+        best
+    }
+
+    /// The half moves playing the larger die of a non-double roll. If only one die of a roll can
+    /// be played, the larger one has to be played whenever possible. `None` for doubles and if
+    /// the larger die cannot be played.
+    #[cfg(feature = "std")]
+    fn larger_die_half_moves(&self, dice: Dice) -> Option<TinyVec<(HalfMove, Dice), 30>> {
+        if dice.is_double() {
+            return None;
+        }
+        let half_moves = self.generate_half_moves(dice.use_die(dice.canonical().0));
+        (!half_moves.is_empty()).then_some(half_moves)
+    }
+
+    /// The die the active player is forced to play if only one die of a non-double roll can be
+    /// used: the only playable one, or the larger one if either could be played but not both.
+    /// `None` if both dice can be used, for doubles and if no checker can be moved.
+    #[cfg(feature = "std")]
+    pub fn forced_die(&self, dice: Dice) -> Option<u8> {
+        if dice.is_double() || self.generate_moves(dice).first().map_or(0, |m| m.len()) != 1 {
+            return None;
+        }
+        let (smaller, larger) = dice.canonical();
+        Some(if self.larger_die_half_moves(dice).is_some() { larger } else { smaller })
+    }
+
+    /// Returns true if both dice of a non-double roll can be played, and each of them could be
+    /// played on its own, but only starting with one of them, because the other order blocks the
+    /// second die. A UI letting the player use one die at a time has to refuse the wrong first die.
+    #[cfg(feature = "std")]
+    pub fn order_constrained(&self, dice: Dice) -> bool {
+        if dice.is_double() {
+            return false;
+        }
+        let (die1, die2) = (dice.die1(), dice.die2());
+        // The dice with only `die` left to play.
+        let only = |die: u8| dice.use_die(if die == die1 { die2 } else { die1 });
+        let completes = |die: u8| {
+            self.generate_half_moves(only(die)).iter().any(|(half_move, _)| {
+                let mut board = *self;
+                board.make_halfmove_unchecked(half_move);
+                !board.generate_half_moves(dice.use_die(die)).is_empty()
+            })
+        };
+        !self.generate_half_moves(only(die1)).is_empty()
+            && !self.generate_half_moves(only(die2)).is_empty()
+            && completes(die1) != completes(die2)
+    }
+
+    /// Number of moves returned by [`Self::generate_moves`], found with the same traversal but
+    /// only keeping the sorted half move codes of each partial move instead of building [`Move`]s.
+    #[cfg(feature = "std")]
+    pub fn legal_move_count(&self, dice: Dice) -> usize {
+        let mut layer: Vec<(Dice, Board, [u16; 4])> = vec![(dice, *self, [0; 4])];
+        let mut next_layer = Vec::new();
+        let mut seen = HashSet::new();
+
+        for depth in 0..4 {
+            seen.clear();
+            for &(dice, board, codes) in layer.iter() {
+                if dice.all_used() {
+                    continue;
+                }
+                for &(half_move, remaining_dice) in board.generate_half_moves(dice).iter() {
+                    let mut codes = codes;
+                    codes[depth] = (half_move.from().get() as u16) << 8 | half_move.to().get() as u16;
+                    codes[..=depth].sort_unstable();
+                    if seen.insert(codes) {
+                        let mut board = board;
+                        board.make_halfmove_unchecked(&half_move);
+                        next_layer.push((remaining_dice, board, codes));
+                    }
+                }
+            }
+            if next_layer.is_empty() {
+                break;
+            }
+            core::mem::swap(&mut layer, &mut next_layer);
+            next_layer.clear();
+        }
+        let single_half_move = layer[0].2[0] != 0 && layer[0].2[1] == 0;
+        if single_half_move && let Some(larger) = self.larger_die_half_moves(dice) {
+            return larger.len() as usize;
+        }
+        layer.len()
+    }
+
+    /// Uniformly random move from the set returned by [`Self::generate_moves`], or `None` if no
+    /// checker can be moved. Traverses like [`Self::generate_moves`] but only keeps a reservoir
+    /// sample of each layer instead of collecting the finished moves.
+    #[cfg(feature = "std")]
+    pub fn random_move<R: Rng>(&self, dice: Dice, rng: &mut R) -> Option<Move> {
+        let mut layer: Vec<(Dice, Board, Move)> = vec![(dice, *self, Move::new())];
+        let mut next_layer = Vec::new();
+        let mut seen = HashSet::new();
+        let mut sample = None;
+
+        loop {
+            seen.clear();
+            let mut next_sample = None;
+            for (dice, board, m) in layer.iter() {
+                if dice.all_used() {
+                    continue;
+                }
+                for &(half_move, remaining_dice) in board.generate_half_moves(*dice).iter() {
+                    let mut m = *m;
+                    m.add_half_move(half_move);
+                    if seen.insert(m.unordered_key()) {
+                        if rng.random_range(0..seen.len()) == 0 {
+                            next_sample = Some(m);
+                        }
+                        let mut board = *board;
+                        board.make_halfmove_unchecked(&half_move);
+                        next_layer.push((remaining_dice, board, m));
+                    }
+                }
+            }
+            if next_layer.is_empty() {
+                break;
+            }
+            sample = next_sample;
+            core::mem::swap(&mut layer, &mut next_layer);
+            next_layer.clear();
+        }
+        if layer[0].2.len() == 1 && let Some(larger) = self.larger_die_half_moves(dice) {
+            let mut m = Move::new();
+            m.add_half_move(larger[rng.random_range(0..larger.len() as usize)].0);
+            return Some(m);
+        }
+        sample
+    }
+
+    /// Positions a checker starting on `from` can be moved to with `dice` as part of a legal move,
+    /// including the points reached by combining dice and bearing off to [`Position::Home`].
+    /// This is what a UI highlights once the player selected a checker.
+    #[cfg(feature = "std")]
+    pub fn destinations(&self, from: Position, dice: Dice) -> TinyVec<Position, 4> {
+        let mut destinations = TinyVec::new();
+        let mut add = |position: Position| {
+            if !destinations.contains(&position) {
+                destinations.push(position);
+            }
+        };
+        for m in self.generate_moves(dice) {
+            for (start, half_move) in m.iter().enumerate() {
+                if Position::from(*half_move.from()) != from {
+                    continue;
+                }
+                // Follow the checker through the later half moves continuing from where it landed.
+                let mut position = Position::from(*half_move.to());
+                add(position);
+                for next in m.iter().skip(start + 1) {
+                    if Position::from(*next.from()) == position {
+                        position = Position::from(*next.to());
+                        add(position);
+                    }
+                }
+            }
+        }
+        destinations
+    }
+
+    /// Returns true if no more contact is possible, because the rearmost checker of the active
+    /// player has passed the rearmost checker of the passive player.
+    pub fn is_race(&self) -> bool {
+        if self.get_active_bar() > 0 || self.get_passive_bar() > 0 {
+            return false;
+        }
+        let rearmost_active = (0..24).find(|&i| self.get_checkers_on_position(i) > 0);
+        let rearmost_passive = (0..24).rev().find(|&i| self.get_checkers_on_position(i) < 0);
+        match (rearmost_active, rearmost_passive) {
+            (Some(active), Some(passive)) => active > passive,
+            _ => true,
+        }
+    }
+
+    /// Classifies the position, checking for a closeout first, then for contact, see [`GamePhase`].
+    pub fn phase(&self) -> GamePhase {
+        if self.is_closed_out(Player::White) || self.is_closed_out(Player::Black) {
+            GamePhase::Closeout
+        } else if !self.is_race() {
+            GamePhase::Contact
+        } else if self.active_player_can_bear_off() && (6..24).all(|i| self.get_checkers_on_position(i) >= 0) {
+            GamePhase::Bearoff
+        } else {
+            GamePhase::Race
+        }
+    }
+
+    /// Length of [`Self::to_feature_vector`].
+    pub const FEATURE_COUNT: usize = 196;
+
+    /// Encodes the board for a neural network evaluator, from the perspective of the active player.
+    ///
+    /// Every point takes 4 inputs: `1.0` each for at least 1, 2 and 3 checkers and
+    /// `(n - 3) / 2` for the checkers beyond the third. The layout is
+    /// - `0..96`: the active player's checkers, by board index 0 to 23 (their 24 to 1 point),
+    /// - `96..192`: the passive player's checkers, mirrored so that they also run from their 24 to 1 point,
+    /// - `192`, `193`: the checkers on the bar of the active and passive player, divided by 2,
+    /// - `194`, `195`: the borne off checkers of the active and passive player, divided by 15.
+    pub fn to_feature_vector(&self) -> [f32; Self::FEATURE_COUNT] {
+        fn encode(count: u8, inputs: &mut [f32]) {
+            inputs[0] = (count >= 1) as u8 as f32;
+            inputs[1] = (count >= 2) as u8 as f32;
+            inputs[2] = (count >= 3) as u8 as f32;
+            inputs[3] = count.saturating_sub(3) as f32 / 2.0;
+        }
+
+        let mut features = [0.0; Self::FEATURE_COUNT];
+        for index in 0..24 {
+            let checkers = self.get_checkers_on_position(index);
+            if checkers > 0 {
+                let offset = 4 * index as usize;
+                encode(checkers as u8, &mut features[offset..offset + 4]);
+            } else if checkers < 0 {
+                let offset = 96 + 4 * (23 - index as usize);
+                encode(checkers.unsigned_abs(), &mut features[offset..offset + 4]);
+            }
+        }
+        features[192] = self.get_active_bar() as f32 / 2.0;
+        features[193] = self.get_passive_bar() as f32 / 2.0;
+        features[194] = self.get_active_home() as f32 / 15.0;
+        features[195] = self.get_passive_home() as f32 / 15.0;
+        features
+    }
+
+    /// Exact cubeless equity for the active player from the bear-off database, counting every
+    /// win as a single game. Returns `None` unless both players have all remaining checkers
+    /// in their home boards.
+    #[cfg(feature = "std")]
+    pub fn bearoff_equity(&self) -> Option<f32> {
+        if self.get_active_bar() > 0 || self.get_passive_bar() > 0 {
+            return None;
+        }
+        if (6..18).any(|i| self.get_checkers_on_position(i) != 0)
+            || (0..6).any(|i| self.get_checkers_on_position(i) > 0)
+            || (18..24).any(|i| self.get_checkers_on_position(i) < 0) {
+            return None;
+        }
+        // Ace points are index 23 for the active and index 0 for the passive player.
+        let active: bearoff::HomeBoard = core::array::from_fn(|p| self.get_count_on_position(23 - p as u8));
+        let passive: bearoff::HomeBoard = core::array::from_fn(|p| self.get_count_on_position(p as u8));
+        let win_probability = bearoff::database().win_probability(&active, &passive);
+        Some(2.0 * win_probability - 1.0)
+    }
+
+    /// Probability that the active player needs exactly `k` rolls to bear off all checkers,
+    /// indexed by `k`, from the bear-off database. Returns `None` unless all checkers of the
+    /// active player are in the home board and there is no contact.
+    #[cfg(feature = "std")]
+    pub fn bearoff_roll_distribution(&self) -> Option<Vec<f32>> {
+        if !self.is_race() || !self.active_player_can_bear_off() {
+            return None;
+        }
+        let active: bearoff::HomeBoard = core::array::from_fn(|p| self.get_count_on_position(23 - p as u8));
+        let distribution = bearoff::database().roll_distribution(&active);
+        let rolls = distribution.iter().rposition(|&p| p > 0.0).map_or(1, |last| last + 1);
+        Some(distribution[..rolls].to_vec())
+    }
+
     pub fn active_player_can_bear_off(&self) -> bool {
         let sum = (18..24)
             .map(|i| self.get_checkers_on_position(i))
@@ -212,62 +1127,46 @@ impl Board {
         sum + self.get_active_home() as i8 == 15
     }
 
-    pub fn generate_half_moves(&self, dice: Dice) -> TinyVec<(HalfMove, Dice), 15> {
+    pub fn generate_half_moves(&self, dice: Dice) -> TinyVec<(HalfMove, Dice), 30> {
         let available_dice = dice.availiable_dice();
         let mut half_moves = TinyVec::new();
 
         if self.get_active_bar() == 0 {
+            let (active, blocked) = self.occupancy_masks();
             for &die in available_dice.iter() {
-                for i in 0..(24 - die as usize) {
-                    if self.get_checkers_on_position(i as u8) > 0 && self.get_checkers_on_position(i as u8 + die) >= -1 {
-                        half_moves.push((
-                            HalfMove::from_compressed(
-                                PositionCompressed::from_index(i as u8),
-                                PositionCompressed::from_index(i as u8 + die),
-                            ),
-                            dice.use_die(die)
-                        ));
-                    }
-                }        
+                // Points with an active checker whose target `die` points ahead is on the board and not blocked.
+                let mut sources = active & !(blocked >> die) & ((1 << (24 - die)) - 1);
+                while sources != 0 {
+                    let i = sources.trailing_zeros() as u8;
+                    sources &= sources - 1;
+                    half_moves.push((
+                        HalfMove::from_compressed(
+                            PositionCompressed::from_index(i),
+                            PositionCompressed::from_index(i + die),
+                        ),
+                        dice.use_die(die)
+                    ));
+                }
             }
             if self.active_player_can_bear_off() {
                 for &die in available_dice.iter() {
-                    let indx = 6 - die as usize;
-                    if self.get_checkers_on_position(18 + indx as u8) > 0 {
+                    if let Some(index) = self.bear_off_index(die) {
                         half_moves.push((
                             HalfMove::from_compressed(
-                                PositionCompressed::from_index(24 - die),
+                                PositionCompressed::from_index(index),
                                 PositionCompressed::HOME,
                             ),
                             dice.use_die(die),
                         ));
                     }
                 }
-                if half_moves.is_empty() {
-                    for &die in available_dice.iter() {
-                        let indx = 6 - die as usize;
-                        for (i,v) in self.active_home_board().skip(indx).enumerate() {
-                            if v > 0 {
-                                half_moves.push((
-                                    HalfMove::from_compressed(
-                                        PositionCompressed::from_index(18 + indx as u8 + i as u8),
-                                        PositionCompressed::HOME,
-                                    ),
-                                    dice.use_die(die),
-                                ));
-                            }
-                        }
-                    }
-                }
             }
         } else {
             for &die in available_dice.iter() {
-                if self.get_checkers_on_position(die - 1) >= -1 {
+                let target = Position::Bar.advance(die);
+                if let Position::Board(index) = target && self.get_checkers_on_position(index) >= -1 {
                     half_moves.push((
-                        HalfMove::from_compressed(
-                            PositionCompressed::BAR,
-                            PositionCompressed::from_index(die - 1),
-                        ),
+                        HalfMove::from_compressed(PositionCompressed::BAR, target.into()),
                         dice.use_die(die),
                     ));
                 }
@@ -278,31 +1177,114 @@ impl Board {
     }
 
 
+    /// Bitmasks with bit `i` set for point index `i`: the points holding at least one active
+    /// checker and the points blocked by at least two passive checkers. The counts of all
+    /// points of a board word are tested at once before the bits are gathered by index.
+    fn occupancy_masks(&self) -> (u32, u32) {
+        const LOWEST_COUNT_BITS: u64 = Board::INVERT_SIGN_MASK >> 4;
+        let [active, blocked] = self.board.map(|word| {
+            let counts = word & (LOWEST_COUNT_BITS * 0xF);
+            let at_least_two = (counts >> 1 | counts >> 2 | counts >> 3) & LOWEST_COUNT_BITS;
+            let occupied = (counts & LOWEST_COUNT_BITS) | at_least_two;
+            let passive = (word >> 4) & LOWEST_COUNT_BITS;
+            (Self::gather_point_bits(occupied & !passive), Self::gather_point_bits(at_least_two & passive))
+        });
+        // The first word stores index 0 at the highest offset, so its bits are reversed.
+        let combine = |first: u32, second: u32| first.reverse_bits() >> 20 | second << 12;
+        (combine(active.0, blocked.0), combine(active.1, blocked.1))
+    }
+
+    /// Moves the lowest count bit of the 12 points of a board word, bit `4 + 5 * j`, to bit `j`
+    /// by merging neighbouring points in three rounds of doubling width.
+    fn gather_point_bits(fields: u64) -> u32 {
+        let x = fields >> 4;
+        let x = (x | x >> 4) & 0x000C_0300_C030_0C03;
+        let x = (x | x >> 8) & 0x0000_0F00_00F0_000F;
+        let x = x | x >> 16;
+        ((x & 0xFF) | (x >> 32 & 0xF00)) as u32
+    }
+
+    /// Index of the checker that can be borne off with `die`, assuming all checkers are home.
+    fn bear_off_index(&self, die: u8) -> Option<u8> {
+        let exact = 24 - die;
+        if self.get_checkers_on_position(exact) > 0 {
+            return Some(exact);
+        }
+        // A larger die may only bear off from the highest occupied point,
+        // and only if no checker sits on a point higher than the die value.
+        if (18..exact).any(|i| self.get_checkers_on_position(i) > 0) {
+            return None;
+        }
+        (exact + 1..24).find(|&i| self.get_checkers_on_position(i) > 0)
+    }
+
+    /// The half moves entering a checker from the bar with one of the available dice.
+    /// Empty if the active player has no checker on the bar.
+    pub fn bar_entry_moves(&self, dice: Dice) -> TinyVec<HalfMove, 2> {
+        let mut entries = TinyVec::new();
+        if self.get_active_bar() == 0 {
+            return entries;
+        }
+        for &die in dice.availiable_dice().iter() {
+            let target = Position::Bar.advance(die);
+            if let Position::Board(index) = target && self.get_checkers_on_position(index) >= -1 {
+                entries.push(HalfMove::from_compressed(PositionCompressed::BAR, target.into()));
+            }
+        }
+        entries
+    }
+
+    /// Returns true if at least one checker can be moved with `dice`, stopping at the first
+    /// legal half move. Equivalent to the legal moves of [`Self::generate_moves`] being non-empty.
+    pub fn has_any_move(&self, dice: Dice) -> bool {
+        if self.get_active_bar() > 0 {
+            return !self.bar_entry_moves(dice).is_empty();
+        }
+        let available_dice = dice.availiable_dice();
+        let can_bear_off = self.active_player_can_bear_off();
+        available_dice.iter().any(|&die| {
+            (0..24 - die).any(|i| self.get_checkers_on_position(i) > 0 && self.get_checkers_on_position(i + die) >= -1)
+                || (can_bear_off && self.bear_off_index(die).is_some())
+        })
+    }
+
     pub fn make_halfmove_unchecked(&mut self, half_move: &HalfMove) {
         match half_move.from().get() {
             1 => self.set_active_bar(self.get_active_bar() - 1),
             2 => panic!("Cannot move from home"),
             n => {
-                let count = self.get_checkers_on_position(n - 2);
-                self.set_checkers_on_position(n - 2, count - 1);
+                let count = self.get_checkers_on_position(n - 3);
+                self.set_checkers_on_position(n - 3, count - 1);
             }
         }
         match half_move.to().get() {
             1 => panic!("Cannot move to bar"),
             2 => self.set_active_home(self.get_active_home() + 1),
             n => {
-                let mut count = self.get_checkers_on_position(n - 2);
+                let mut count = self.get_checkers_on_position(n - 3);
                 if count >= -1 {
                     if count == -1 {
                         self.set_passive_bar(self.get_passive_bar() + 1);
                         count = 0;
                     }
-                    self.set_checkers_on_position(n - 2, count + 1);
+                    self.set_checkers_on_position(n - 3, count + 1);
                 }
             }
         }
     }
 
+    /// Plays a half move like [`Self::make_halfmove_unchecked`] and returns the index of the point
+    /// where it hit a blot of the passive player, which is then on the passive bar.
+    /// Prefer [`Self::make_halfmove_unchecked`] in hot loops that do not need the hit.
+    pub fn apply_half_move(&mut self, half_move: &HalfMove) -> Option<u8> {
+        let hit = match Position::from(*half_move.to()) {
+            Position::Board(index) if self.get_checkers_on_position(index) == -1 => Some(index),
+            _ => None,
+        };
+        self.make_halfmove_unchecked(half_move);
+        hit
+    }
+
     pub fn make_move_unchecked(&mut self, full_move: Move) {
         for half_move in full_move.iter() {
             self.make_halfmove_unchecked(half_move);
@@ -310,4 +1292,1319 @@ impl Board {
         self.switch_player();
     }
 
-}
\ No newline at end of file
+    /// Plays a move after verifying that it is legal for the given dice.
+    /// The board is left untouched if an error is returned.
+    #[cfg(feature = "std")]
+    pub fn make_move(&mut self, full_move: Move, dice: Dice) -> Result<(), MoveError> {
+        if self.outcome() != GameOutcome::Ongoing {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        if !full_move.iter().all(|half_move| Self::half_move_matches_dice(half_move, dice)) {
+            return Err(MoveError::WrongDice);
+        }
+
+        // Moves compare equal regardless of the order of their half moves, so the generated move
+        // is played, the order given by the caller may be blocked, e.g. `22/20(2) 24/22` with 2-2.
+        match self.generate_moves(dice).into_iter().find(|legal| *legal == full_move) {
+            Some(legal) => {
+                self.make_move_unchecked(legal);
+                Ok(())
+            }
+            None => Err(MoveError::NotInLegalSet),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn half_move_matches_dice(half_move: &HalfMove, dice: Dice) -> bool {
+        let available_dice = dice.availiable_dice();
+        match (Position::from(*half_move.from()), Position::from(*half_move.to())) {
+            (Position::Bar, Position::Board(to)) => available_dice.contains(&(to + 1)),
+            (Position::Board(from), Position::Home) => available_dice.iter().any(|&die| die >= 24 - from),
+            (Position::Board(from), Position::Board(to)) if to > from => available_dice.contains(&(to - from)),
+            _ => false,
+        }
+    }
+
+}
+
+/// Depth first traversal behind [`Board::moves_iter`].
+#[cfg(feature = "std")]
+struct MovesIter {
+    stack: Vec<(Dice, Board, Move)>,
+    /// Partial and complete moves already pushed, by [`Move::unordered_key`].
+    seen: HashSet<u64>,
+    /// Number of half moves of every legal move.
+    len: usize,
+    /// Half moves a move of a single half move may consist of, by the larger-die rule.
+    larger: Option<TinyVec<(HalfMove, Dice), 30>>,
+}
+
+#[cfg(feature = "std")]
+impl Iterator for MovesIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        while let Some((dice, board, m)) = self.stack.pop() {
+            if m.len() as usize == self.len {
+                if let Some(larger) = &self.larger
+                    && !larger.iter().any(|(half_move, _)| m.iter().next() == Some(half_move))
+                {
+                    continue;
+                }
+                return Some(m);
+            }
+            for &(half_move, remaining_dice) in board.generate_half_moves(dice).iter() {
+                let mut m = m;
+                m.add_half_move(half_move);
+                if self.seen.insert(m.unordered_key()) {
+                    let mut board = board;
+                    board.make_halfmove_unchecked(&half_move);
+                    self.stack.push((remaining_dice, board, m));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized_board() == other.normalized_board()
+            && self.home == other.home
+            && self.active_player == other.active_player
+    }
+}
+
+impl Eq for Board {}
+
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized_board().hash(state);
+        self.home.hash(state);
+        self.active_player.hash(state);
+    }
+}
+
+/// Logical form of a [`Board`] used for serialization, so that saved positions do not
+/// depend on the bit layout. Points are seen from the active player, positive counts are
+/// the active player's checkers.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedBoard {
+    points: [i8; 24],
+    active_bar: u8,
+    passive_bar: u8,
+    active_home: u8,
+    passive_home: u8,
+    active_player: Player,
+}
+
+#[cfg(feature = "serde")]
+impl From<Board> for SerializedBoard {
+    fn from(board: Board) -> Self {
+        SerializedBoard {
+            points: core::array::from_fn(|i| board.get_checkers_on_position(i as u8)),
+            active_bar: board.get_active_bar(),
+            passive_bar: board.get_passive_bar(),
+            active_home: board.get_active_home(),
+            passive_home: board.get_passive_home(),
+            active_player: board.active_player,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedBoard> for Board {
+    type Error = PositionError;
+
+    fn try_from(serialized: SerializedBoard) -> Result<Self, Self::Error> {
+        Board::from_points(
+            serialized.points,
+            serialized.active_bar,
+            serialized.passive_bar,
+            serialized.active_home,
+            serialized.passive_home,
+            serialized.active_player,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bear_off_board(checkers: &[(u8, u8)]) -> Board {
+        let mut board = Board::empty();
+        let mut total = 0;
+        for &(index, count) in checkers {
+            board.set_active_player_checker_on_position(index, count);
+            total += count;
+        }
+        board.set_active_home(15 - total);
+        board.set_passive_player_checker_on_position(0, 15);
+        board
+    }
+
+    fn single_move(half_moves: &[(u8, u8)]) -> Move {
+        let mut m = Move::new();
+        for &(from, to) in half_moves {
+            m.add_half_move(HalfMove::from_compressed(
+                PositionCompressed::from_index(from),
+                PositionCompressed::from_index(to),
+            ));
+        }
+        m
+    }
+
+    #[test]
+    fn test_make_move_legal() {
+        let mut board = Board::new();
+        assert_eq!(board.make_move(single_move(&[(16, 19), (18, 19)]), Dice::from_numbers(3, 1)), Ok(()));
+        assert_eq!(board.active_player, Player::Black);
+        board.switch_player();
+        assert_eq!(board.get_checkers_on_position(19), 2);
+        assert_eq!(board.get_checkers_on_position(16), 2);
+        assert_eq!(board.get_checkers_on_position(18), 4);
+    }
+
+    #[test]
+    fn test_make_move_illegal_leaves_board_unchanged() {
+        let mut board = Board::new();
+        let dice = Dice::from_numbers(5, 3);
+        assert_eq!(board.make_move(single_move(&[(0, 4), (0, 3)]), dice), Err(MoveError::WrongDice));
+        assert_eq!(board.make_move(single_move(&[(0, 5), (11, 14)]), dice), Err(MoveError::NotInLegalSet));
+        assert_eq!(board.make_move(single_move(&[(11, 16)]), dice), Err(MoveError::NotInLegalSet));
+        assert_eq!(board, Board::new());
+
+        let mut board = Board::empty();
+        board.set_active_home(15);
+        board.set_passive_player_checker_on_position(3, 15);
+        let before = board;
+        assert_eq!(board.make_move(Move::new(), dice), Err(MoveError::GameAlreadyOver));
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn test_make_move_accepts_notation_of_generated_moves() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(23);
+        for _ in 0..8 {
+            let mut board = Board::new();
+            while board.outcome() == GameOutcome::Ongoing {
+                let dice = Dice::roll_with(&mut rng);
+                let moves = board.generate_moves(dice);
+                for &m in &moves {
+                    let notation = m.to_notation(&board);
+                    let (mut played, mut expected) = (board, board);
+                    assert_eq!(played.make_move(notation.parse().unwrap(), dice), Ok(()), "{notation} with {dice}");
+                    expected.make_move_unchecked(m);
+                    assert_eq!(played, expected);
+                }
+                board.make_move_unchecked(*moves.choose(&mut rng).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_zobrist_hash_transposition() {
+        let play = |moves: &[&str]| {
+            let mut board = Board::new();
+            for m in moves {
+                board.make_move_unchecked(m.parse().unwrap());
+            }
+            board
+        };
+
+        let a = play(&["13/11", "13/10", "24/18"]);
+        let b = play(&["24/18", "13/10", "13/11"]);
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+
+        let mut c = Board::new();
+        let mut d = Board::new();
+        let first = HalfMove::from_compressed(PositionCompressed::from_index(0), PositionCompressed::from_index(6));
+        let second = HalfMove::from_compressed(PositionCompressed::from_index(11), PositionCompressed::from_index(13));
+        c.make_halfmove_unchecked(&first);
+        c.make_halfmove_unchecked(&second);
+        d.make_halfmove_unchecked(&second);
+        d.make_halfmove_unchecked(&first);
+        assert_eq!(c.zobrist_hash(), d.zobrist_hash());
+
+        assert_ne!(a.zobrist_hash(), play(&["13/11", "13/10"]).zobrist_hash());
+        let mut switched = Board::new();
+        switched.switch_player();
+        assert_ne!(Board::new().zobrist_hash(), switched.zobrist_hash());
+    }
+
+    #[test]
+    fn test_is_race() {
+        let mut board = Board::new();
+        assert!(!board.is_race());
+        board.switch_player();
+        assert!(!board.is_race());
+
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(19, 3);
+        board.set_active_player_checker_on_position(22, 4);
+        board.set_active_home(8);
+        board.set_passive_player_checker_on_position(2, 5);
+        board.set_passive_player_checker_on_position(15, 1);
+        board.set_passive_home(9);
+        assert!(board.is_race());
+        board.switch_player();
+        assert!(board.is_race());
+
+        board.set_passive_bar(1);
+        assert!(!board.is_race());
+    }
+
+    #[test]
+    fn test_bearoff_equity() {
+        assert_eq!(Board::new().bearoff_equity(), None);
+
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(23, 1);
+        board.set_active_home(14);
+        board.set_passive_player_checker_on_position(0, 1);
+        board.set_passive_home(14);
+        assert_eq!(board.bearoff_equity(), Some(1.0));
+
+        board.set_active_player_checker_on_position(23, 0);
+        board.set_active_player_checker_on_position(18, 1);
+        let equity = board.bearoff_equity().unwrap();
+        assert!((equity - (2.0 * 27.0 / 36.0 - 1.0)).abs() < 1e-5);
+
+        board.set_passive_player_checker_on_position(6, 1);
+        assert_eq!(board.bearoff_equity(), None);
+    }
+
+    #[test]
+    fn test_captured_value() {
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(0, 2);
+        board.set_passive_player_checker_on_position(3, 1);
+        board.set_passive_player_checker_on_position(5, 2);
+        assert_eq!(board.captured_value(&single_move(&[(0, 3), (0, 4)])), 4);
+        assert_eq!(board.captured_value(&single_move(&[(0, 1), (0, 4)])), 0);
+    }
+
+    /// The point by point scan `generate_half_moves` used before the occupancy masks.
+    fn reference_half_moves(board: &Board, dice: Dice) -> Vec<(HalfMove, Dice)> {
+        if board.get_active_bar() > 0 {
+            return board.generate_half_moves(dice).to_vec();
+        }
+        let mut half_moves = Vec::new();
+        for &die in dice.availiable_dice().iter() {
+            for i in 0..24 - die {
+                if board.get_checkers_on_position(i) > 0 && board.get_checkers_on_position(i + die) >= -1 {
+                    half_moves.push((HalfMove::from_compressed(PositionCompressed::from_index(i), PositionCompressed::from_index(i + die)), dice.use_die(die)));
+                }
+            }
+        }
+        if board.active_player_can_bear_off() {
+            for &die in dice.availiable_dice().iter() {
+                if let Some(index) = board.bear_off_index(die) {
+                    half_moves.push((HalfMove::from_compressed(PositionCompressed::from_index(index), PositionCompressed::HOME), dice.use_die(die)));
+                }
+            }
+        }
+        half_moves
+    }
+
+    #[test]
+    fn test_half_moves_match_point_scan() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(17);
+        let mut positions = vec![Board::new(), Board::nackgammon(), bear_off_board(&[(18, 3), (20, 2), (23, 1)])];
+        for _ in 0..30 {
+            let mut board = Board::new();
+            while board.outcome() == GameOutcome::Ongoing {
+                positions.push(board);
+                let moves = board.generate_moves(Dice::roll_with(&mut rng));
+                board.make_move_unchecked(*moves.choose(&mut rng).unwrap());
+            }
+        }
+        for board in positions {
+            for dice in Dice::ALL_ORDERED {
+                assert_eq!(*board.generate_half_moves(dice), *reference_half_moves(&board, dice), "{}", board.to_fancy_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_pretty_unicode() {
+        let count = |s: &str, glyph: char| s.chars().filter(|&c| c == glyph).count();
+        for color in [false, true] {
+            let rendered = Board::new().pretty_unicode(color);
+            assert_eq!(count(&rendered, '●'), 15);
+            assert_eq!(count(&rendered, '○'), 15);
+            assert_eq!(rendered.contains('\x1b'), color);
+        }
+        let rendered = Board::new().pretty_unicode(false);
+        assert!(rendered.lines().take(17).all(|line| line.chars().count() == 56));
+
+        let mut points = [0; 24];
+        points[0] = 8;
+        points[23] = 6;
+        points[12] = -15;
+        let board = Board::from_points(points, 1, 0, 0, 0, Player::White).unwrap();
+        let rendered = board.pretty_unicode(false);
+        assert_eq!(count(&rendered, '●'), 11);
+        assert!(rendered.contains("+3") && rendered.contains("+10"));
+    }
+
+    #[test]
+    fn test_phase() {
+        assert_eq!(Board::new().phase(), GamePhase::Contact);
+
+        // White's rearmost checker has passed Black's, but White still has a checker outside.
+        let mut points = [0; 24];
+        points[12] = 1;
+        points[18..24].copy_from_slice(&[4, 2, 2, 2, 2, 2]);
+        points[0..6].copy_from_slice(&[-3, -3, -3, -2, -2, -2]);
+        let race = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert_eq!(race.phase(), GamePhase::Race);
+
+        points[12] = 0;
+        points[18] = 5;
+        let bearoff = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert_eq!(bearoff.phase(), GamePhase::Bearoff);
+        let mut switched = bearoff;
+        switched.switch_player();
+        assert_eq!(switched.phase(), GamePhase::Bearoff);
+
+        // Black still has a checker outside its home board.
+        points[0] = -2;
+        points[10] = -1;
+        assert_eq!(Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap().phase(), GamePhase::Race);
+
+        let mut points = [0; 24];
+        points[18..24].copy_from_slice(&[2, 2, 2, 2, 2, 3]);
+        points[5] = -2;
+        points[10] = -12;
+        let closeout = Board::from_points(points, 2, 1, 0, 0, Player::White).unwrap();
+        assert!(!closeout.is_race());
+        assert_eq!(closeout.phase(), GamePhase::Closeout);
+    }
+
+    #[test]
+    fn test_destinations() {
+        let board = Board::new();
+        let sorted = |destinations: TinyVec<Position, 4>| {
+            let mut destinations = destinations.to_vec();
+            destinations.sort_by_key(Position::pips_to_home);
+            destinations
+        };
+        // The back checkers cannot play the 5 onto the opponent's 5 checkers, but can continue
+        // with it after playing the 6.
+        let dice = Dice::from_numbers(6, 5);
+        assert_eq!(sorted(board.destinations(Position::Board(0), dice)), [Position::Board(11), Position::Board(6)]);
+        assert_eq!(sorted(board.destinations(Position::Board(11), dice)), [Position::Board(22), Position::Board(17), Position::Board(16)]);
+        assert!(board.destinations(Position::Board(1), dice).is_empty());
+        assert!(board.destinations(Position::Bar, dice).is_empty());
+
+        let board = bear_off_board(&[(19, 1), (22, 2)]);
+        let dice = Dice::from_numbers(2, 1);
+        assert_eq!(sorted(board.destinations(Position::Board(22), dice)), [Position::Home, Position::Board(23)]);
+        assert_eq!(sorted(board.destinations(Position::Board(19), dice)), [Position::Board(22), Position::Board(21), Position::Board(20)]);
+    }
+
+    #[test]
+    fn test_forced_die() {
+        // A single checker that can play either die but not both, the rest is stuck on the last point.
+        let board = |blocked: &[u8]| {
+            let mut points = [0; 24];
+            points[0] = 1;
+            points[23] = 14;
+            points[12] = -15 + 2 * blocked.len() as i8;
+            for &index in blocked {
+                points[index as usize] = -2;
+            }
+            Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap()
+        };
+        let dice = Dice::from_numbers(2, 6);
+
+        let either = board(&[8]);
+        assert_eq!(either.forced_die(dice), Some(6));
+        assert_eq!(either.forced_die(Dice::from_numbers(6, 2)), Some(6));
+        assert_eq!(either.generate_moves(dice).iter().map(|m| m.to_string()).collect::<Vec<_>>(), ["24/18"]);
+        assert_eq!(either.legal_move_count(dice), 1);
+        assert_eq!(either.random_move(dice, &mut rand::rng()).unwrap().to_string(), "24/18");
+        let mut played = either;
+        assert_eq!(played.make_move("24/22".parse().unwrap(), dice), Err(MoveError::NotInLegalSet));
+        played.make_move("24/18".parse().unwrap(), dice).unwrap();
+
+        // Must play the 6, can't play the 2.
+        assert_eq!(board(&[2, 8]).forced_die(dice), Some(6));
+        assert_eq!(board(&[6, 8]).forced_die(dice), Some(2));
+        assert_eq!(board(&[2, 6]).forced_die(dice), None);
+        assert_eq!(board(&[]).forced_die(dice), None);
+        assert_eq!(board(&[]).forced_die(Dice::from_numbers(3, 3)), None);
+        assert_eq!(Board::new().forced_die(dice), None);
+
+        let legacy = crate::game::Board::from(either);
+        assert_eq!(legacy.generate_moves(crate::game::Dice::new(2, 6)).len(), 1);
+    }
+
+    #[test]
+    fn test_order_constrained() {
+        // Entering with the 6 leaves the 2 for the checker on 14, entering with the 2 leaves no 6:
+        // 17 and 8 are blocked.
+        let mut points = [0; 24];
+        points[10] = 1;
+        points[23] = 13;
+        points[7] = -2;
+        points[16] = -2;
+        points[20] = -11;
+        let board = Board::from_points(points, 1, 0, 0, 0, Player::White).unwrap();
+        let dice = Dice::from_numbers(2, 6);
+        assert!(board.order_constrained(dice));
+        assert!(board.order_constrained(Dice::from_numbers(6, 2)));
+        let moves = board.generate_moves(dice);
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|m| m.len() == 2 && Position::from(*m.iter().next().unwrap().to()) == Position::Board(5)));
+
+        // With 12 blocked as well only one die can be played.
+        points[12] = -2;
+        points[20] = -9;
+        let blocked = Board::from_points(points, 1, 0, 0, 0, Player::White).unwrap();
+        assert!(!blocked.order_constrained(dice));
+
+        for dice in Dice::ALL {
+            assert!(!Board::new().order_constrained(dice));
+        }
+    }
+
+    #[test]
+    fn test_hits() {
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(0, 2);
+        board.set_passive_player_checker_on_position(1, 1);
+        board.set_passive_player_checker_on_position(3, 1);
+        board.set_passive_player_checker_on_position(5, 2);
+        let double_hit = single_move(&[(0, 3), (0, 1)]);
+        assert_eq!(*board.hits(&double_hit), [3, 1]);
+        assert!(board.is_hitting(&double_hit));
+
+        let hit_on_the_way = single_move(&[(0, 1), (1, 4)]);
+        assert_eq!(*board.hits(&hit_on_the_way), [1]);
+        assert!(!board.is_hitting(&single_move(&[(0, 2), (0, 4)])));
+        assert_eq!(board.get_checkers_on_position(1), -1);
+    }
+
+    #[test]
+    fn test_generate_moves_dedup_matches_unordered_equal() {
+        // Counts produced by the previous quadratic `unordered_equal` deduplication.
+        for ((a, b), expected) in [((6, 6), 11), ((1, 1), 42), ((3, 1), 19), ((6, 5), 8)] {
+            let moves = Board::new().generate_moves(Dice::from_numbers(a, b));
+            assert_eq!(moves.len(), expected);
+            for (i, m1) in moves.iter().enumerate() {
+                assert!(moves[i + 1..].iter().all(|m2| !m1.unordered_equal(m2)));
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut points = [0; 24];
+        points[18] = 5;
+        points[20] = 7;
+        points[2] = -11;
+        let mid_game = Board::from_points(points, 1, 1, 2, 3, Player::Black).unwrap();
+        for board in [Board::new(), mid_game] {
+            let json = serde_json::to_string(&board).unwrap();
+            let deserialized = serde_json::from_str::<Board>(&json).unwrap();
+            assert_eq!(deserialized, board);
+        }
+
+        let json = serde_json::to_value(Board::new()).unwrap();
+        assert_eq!(json["points"][0], 2);
+        assert_eq!(json["points"][23], -2);
+        assert_eq!(json["active_player"], "White");
+
+        let invalid = serde_json::to_string(&Board::new()).unwrap().replace("\"active_bar\":0", "\"active_bar\":1");
+        assert!(serde_json::from_str::<Board>(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_from_points() {
+        let mut points = [0; 24];
+        points[18] = 5;
+        points[20] = 7;
+        points[2] = -12;
+        let board = Board::from_points(points, 1, 0, 2, 3, Player::Black).unwrap();
+        assert_eq!(board.get_checkers_on_position(20), 7);
+        assert_eq!(board.get_checkers_on_position(2), -12);
+        assert_eq!(board.get_active_bar(), 1);
+        assert_eq!(board.get_passive_home(), 3);
+        assert_eq!(board.active_player, Player::Black);
+
+        let mut opening = [0; 24];
+        for index in 0..24 {
+            opening[index as usize] = Board::new().get_checkers_on_position(index);
+        }
+        assert_eq!(Board::from_points(opening, 0, 0, 0, 0, Player::White), Ok(Board::new()));
+    }
+
+    #[test]
+    fn test_validate_rejects_over_stacked_point() {
+        assert_eq!(Board::new().validate(), Ok(()));
+
+        let mut board = Board::new();
+        board.set_active_player_checker_on_position(18, 6);
+        assert_eq!(board.validate(), Err(PositionError::WrongCheckerCount { player: Player::White, count: 16 }));
+
+        // 16 checkers do not fit into the 4 bit count and turn into an empty point.
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(18, 16);
+        board.set_passive_player_checker_on_position(5, 15);
+        assert_eq!(board.validate(), Err(PositionError::WrongCheckerCount { player: Player::White, count: 0 }));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invalid position")]
+    fn test_generate_moves_checks_position() {
+        let mut board = Board::new();
+        board.set_active_player_checker_on_position(18, 6);
+        board.generate_moves(Dice::from_numbers(3, 1));
+    }
+
+    #[test]
+    fn test_from_points_rejects_invalid_positions() {
+        let mut points = [0; 24];
+        points[18] = 5;
+        points[20] = 7;
+        points[2] = -12;
+        assert_eq!(
+            Board::from_points(points, 2, 0, 2, 3, Player::White),
+            Err(PositionError::WrongCheckerCount { player: Player::White, count: 16 })
+        );
+        assert_eq!(
+            Board::from_points(points, 1, 1, 2, 3, Player::White),
+            Err(PositionError::WrongCheckerCount { player: Player::Black, count: 16 })
+        );
+        points[20] = 16;
+        assert_eq!(
+            Board::from_points(points, 0, 0, 0, 3, Player::White),
+            Err(PositionError::TooManyCheckersOnPoint { index: 20, count: 16 })
+        );
+        points[20] = 7;
+        assert_eq!(
+            Board::from_points(points, 0, 0, 16, 3, Player::White),
+            Err(PositionError::TooManyCheckersOffBoard { field: "active home", count: 16 })
+        );
+        assert_eq!(
+            Board::from_points(points, 0, 20, 3, 3, Player::White),
+            Err(PositionError::TooManyCheckersOffBoard { field: "passive bar", count: 20 })
+        );
+    }
+
+    #[test]
+    fn test_from_whites_perspective() {
+        assert_eq!(Board::new().from_whites_perspective(), Board::new());
+
+        let mut board = Board::new();
+        board.make_move(single_move(&[(16, 19), (18, 19)]), Dice::from_numbers(3, 1)).unwrap();
+        board.set_passive_bar(1);
+        let white = board.from_whites_perspective();
+        assert_eq!(white.active_player, Player::Black);
+        // White's 8, 6 and 5 points after playing 8/5 6/5.
+        assert_eq!(white.get_checkers_on_position(16), 2);
+        assert_eq!(white.get_checkers_on_position(18), 4);
+        assert_eq!(white.get_checkers_on_position(19), 2);
+        assert_eq!(white.get_checkers_on_position(23), -2);
+        assert_eq!(white.get_active_bar(), 1);
+        assert_eq!(white.get_passive_bar(), 0);
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let mut board = Board::new();
+        board.make_move(single_move(&[(0, 3), (0, 4)]), Dice::from_numbers(3, 4)).unwrap();
+        board.set_passive_bar(1);
+        let mirrored = board.mirrored();
+        assert_eq!(mirrored.active_player, board.active_player);
+        assert_eq!(mirrored.get_active_bar(), 1);
+        for index in 0..24 {
+            assert_eq!(mirrored.get_checkers_on_position(index), -board.get_checkers_on_position(23 - index));
+        }
+        assert_eq!(mirrored.mirrored(), board);
+        // The opening is symmetric, empty points only differ in their unused sign bit.
+        assert!(Board::new().mirrored().occupied().eq(Board::new().occupied()));
+    }
+
+    #[test]
+    fn test_dedup_moves() {
+        let moves = vec![
+            single_move(&[(0, 4), (11, 15)]),
+            single_move(&[(16, 20), (18, 22)]),
+            single_move(&[(11, 15), (0, 4)]),
+            single_move(&[(0, 4), (0, 4), (11, 15), (11, 15)]),
+            single_move(&[(11, 15), (0, 4), (11, 15), (0, 4)]),
+            single_move(&[(0, 4), (11, 15), (11, 15)]),
+        ];
+        let deduped = Board::dedup_moves(moves.clone());
+        assert_eq!(deduped.len(), 4);
+        assert!(deduped.iter().zip([0, 1, 3, 5]).all(|(m, i)| m.iter().eq(moves[i].iter())));
+
+        let dice = Dice::from_numbers(2, 2);
+        let generated = Board::new().generate_moves(dice);
+        assert_eq!(Board::dedup_moves(generated.clone()).len(), generated.len());
+    }
+
+    #[test]
+    fn test_apply_half_move() {
+        let mut points = [0; 24];
+        points[0] = 15;
+        points[3] = -1;
+        points[10] = -14;
+        let mut board = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        let hit = HalfMove::from_compressed(PositionCompressed::from_index(0), PositionCompressed::from_index(3));
+        assert_eq!(board.apply_half_move(&hit), Some(3));
+        assert_eq!(board.get_passive_bar(), 1);
+        assert_eq!(board.get_checkers_on_position(3), 1);
+
+        let quiet = HalfMove::from_compressed(PositionCompressed::from_index(0), PositionCompressed::from_index(5));
+        assert_eq!(board.apply_half_move(&quiet), None);
+        assert_eq!(board.get_passive_bar(), 1);
+    }
+
+    #[test]
+    fn test_from_diagram() {
+        let opening = "
+            24: W2
+            13: W5
+            8: W3
+            6: W5
+            24: B2
+            13: B5
+            8: B3
+            6: B5
+        ";
+        assert_eq!(Board::from_diagram(opening), Ok(Board::new()));
+        let black_to_move = Board::from_diagram(&format!("{}\nactive: Black", opening)).unwrap();
+        let mut expected = Board::new();
+        expected.set_active_player(Player::Black);
+        assert_eq!(black_to_move, expected);
+
+        let race = Board::from_diagram("6: W10\nbar: W1\noff: W4\n1: B15\nactive: Black").unwrap();
+        let mut points = [0; 24];
+        points[23] = 15;
+        points[5] = -10;
+        assert_eq!(race, Board::from_points(points, 0, 1, 0, 4, Player::Black).unwrap());
+
+        assert_eq!(Board::from_diagram("24: W2"), Err(ParseDiagramError::InvalidPosition(PositionError::WrongCheckerCount { player: Player::White, count: 2 })));
+        assert_eq!(Board::from_diagram("25: W2"), Err(ParseDiagramError::Malformed("25: W2".to_string())));
+        assert_eq!(Board::from_diagram("24: W2\n1: B2"), Err(ParseDiagramError::Malformed("1: B2".to_string())));
+        assert_eq!(Board::from_diagram("active: Red"), Err(ParseDiagramError::Malformed("active: Red".to_string())));
+    }
+
+    #[test]
+    fn test_nackgammon() {
+        let board = Board::nackgammon();
+        assert_eq!(board.checker_count(Player::White), 15);
+        assert_eq!(board.checker_count(Player::Black), 15);
+        assert_eq!(board.get_checkers_on_position(1), 2);
+        assert_eq!(board.get_checkers_on_position(22), -2);
+        for (dice, _) in Dice::ALL_WITH_PROBABILITY {
+            let moves = board.generate_moves(dice);
+            assert!(!moves.is_empty() && moves.iter().all(|m| m.iter().next().is_some()), "{dice}");
+        }
+    }
+
+    #[test]
+    fn test_has_any_move_closed_out() {
+        let mut points = [0; 24];
+        points[0..6].copy_from_slice(&[-2, -2, -2, -2, -2, -3]);
+        points[10] = 14;
+        let board = Board::from_points(points, 1, 2, 0, 0, Player::White).unwrap();
+        for dice in Dice::ALL {
+            assert!(!board.has_any_move(dice));
+            assert!(board.generate_moves(dice).iter().all(|m| m.iter().next().is_none()));
+        }
+    }
+
+    #[test]
+    fn test_has_any_move_agrees_with_generate_moves() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..20 {
+            let mut board = Board::new();
+            while board.outcome() == GameOutcome::Ongoing {
+                for (dice, _) in Dice::ALL_WITH_PROBABILITY {
+                    let can_move = board.generate_moves(dice).iter().any(|m| m.iter().next().is_some());
+                    assert_eq!(board.has_any_move(dice), can_move);
+                }
+                let moves = board.generate_moves(Dice::roll_with(&mut rng));
+                board.make_move_unchecked(*moves.choose(&mut rng).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_pass_turn_twice() {
+        let mut board = Board::new();
+        board.make_move("8/5 6/5".parse().unwrap(), Dice::from_numbers(3, 1)).unwrap();
+        let original = board;
+        board.pass_turn();
+        assert_eq!(board.active_player(), original.active_player().opposite());
+        board.pass_turn();
+        assert_eq!(board, original);
+    }
+
+    #[test]
+    fn test_feature_vector_opening() {
+        let mut expected = [0.0; Board::FEATURE_COUNT];
+        for offset in [0, 96] {
+            // 2 checkers on the 24 point, 5 on the 13, 3 on the 8 and 5 on the 6 point.
+            expected[offset..offset + 2].copy_from_slice(&[1.0, 1.0]);
+            expected[offset + 44..offset + 48].copy_from_slice(&[1.0, 1.0, 1.0, 1.0]);
+            expected[offset + 64..offset + 67].copy_from_slice(&[1.0, 1.0, 1.0]);
+            expected[offset + 72..offset + 76].copy_from_slice(&[1.0, 1.0, 1.0, 1.0]);
+        }
+        assert_eq!(Board::new().to_feature_vector(), expected);
+
+        let mut board = Board::new();
+        board.switch_player();
+        assert_eq!(board.to_feature_vector(), expected);
+    }
+
+    #[test]
+    fn test_bearoff_roll_distribution() {
+        assert_eq!(Board::new().bearoff_roll_distribution(), None);
+
+        // Two checkers on the 2 point are borne off in one roll unless it contains a single 1.
+        let mut points = [0; 24];
+        points[22] = 2;
+        points[0] = -15;
+        let board = Board::from_points(points, 0, 0, 13, 0, Player::White).unwrap();
+        let distribution = board.bearoff_roll_distribution().unwrap();
+        assert_eq!(distribution.len(), 3);
+        for (p, expected) in distribution.iter().zip([0.0, 26.0 / 36.0, 10.0 / 36.0]) {
+            assert!((p - expected).abs() < 1e-6, "{distribution:?}");
+        }
+    }
+
+    #[test]
+    fn test_legal_move_count_matches_generate_moves() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut boards = vec![Board::new(), Board::nackgammon()];
+        for _ in 0..5 {
+            let mut board = Board::new();
+            for _ in 0..30 {
+                if board.outcome() != GameOutcome::Ongoing {
+                    break;
+                }
+                let moves = board.generate_moves(Dice::roll_with(&mut rng));
+                board.make_move_unchecked(*moves.choose(&mut rng).unwrap());
+                boards.push(board);
+            }
+        }
+        for board in boards {
+            for dice in Dice::ALL {
+                assert_eq!(board.legal_move_count(dice), board.generate_moves(dice).len(), "{dice}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_moves_iter_matches_generate_moves() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut points = [0; 24];
+        points[0..6].copy_from_slice(&[-2, -2, -2, -2, -2, -3]);
+        points[10] = 14;
+        let closed_out = Board::from_points(points, 1, 2, 0, 0, Player::White).unwrap();
+        let mut boards = vec![Board::new(), Board::nackgammon(), closed_out];
+        for _ in 0..5 {
+            let mut board = Board::new();
+            for _ in 0..40 {
+                if board.outcome() != GameOutcome::Ongoing {
+                    break;
+                }
+                let moves = board.generate_moves(Dice::roll_with(&mut rng));
+                board.make_move_unchecked(*moves.choose(&mut rng).unwrap());
+                boards.push(board);
+            }
+        }
+        for board in boards {
+            for dice in Dice::ALL_ORDERED {
+                let lazy: Vec<Move> = board.moves_iter(dice).collect();
+                let generated = board.generate_moves(dice);
+                assert_eq!(lazy.len(), generated.len(), "{dice}");
+                assert_eq!(lazy.iter().collect::<HashSet<_>>(), generated.iter().collect::<HashSet<_>>(), "{dice}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_mirror_move() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut board = Board::new();
+        for _ in 0..40 {
+            if board.outcome() != GameOutcome::Ongoing {
+                break;
+            }
+            let dice = Dice::roll_with(&mut rng);
+            let m = *board.generate_moves(dice).choose(&mut rng).unwrap();
+            let mirrored = Board::mirror_move(&m);
+            assert!(Board::mirror_move(&mirrored).iter().eq(m.iter()));
+
+            // Seen from White a move only changes the points it moves from or to, so the moves
+            // of Black have to be mirrored to line up with `from_whites_perspective`.
+            let mut played = board;
+            played.make_move(m, dice).unwrap();
+            let (before, after) = (board.from_whites_perspective(), played.from_whites_perspective());
+            let (white_view, sign) = match board.active_player() {
+                Player::White => (m, 1),
+                Player::Black => (mirrored, -1),
+            };
+            let on_board = |position: &PositionCompressed| match Position::from(*position) {
+                Position::Board(index) => Some(index),
+                _ => None,
+            };
+            let from = white_view.iter().filter_map(|half_move| on_board(half_move.from())).collect::<Vec<_>>();
+            let to = white_view.iter().filter_map(|half_move| on_board(half_move.to())).collect::<Vec<_>>();
+            assert!(from.iter().all(|&index| to.contains(&index) || before.get_checkers_on_position(index) * sign > 0));
+            assert!((0..24)
+                .filter(|&index| before.get_checkers_on_position(index) != after.get_checkers_on_position(index))
+                .all(|index| from.contains(&index) || to.contains(&index)));
+
+            board = played;
+        }
+    }
+
+    #[test]
+    fn test_bar_entry_moves() {
+        // Black holds its 1, 3 and 5 points and has a blot on its 2 point.
+        let mut points = [0; 24];
+        points[0..5].copy_from_slice(&[-2, -1, -2, 0, -2]);
+        points[10] = -8;
+        points[18] = 14;
+        let board = Board::from_points(points, 1, 0, 0, 0, Player::White).unwrap();
+        let entries = |die1, die2| -> Vec<Position> {
+            board.bar_entry_moves(Dice::from_numbers(die1, die2)).iter().map(|hm| Position::from(*hm.to())).collect()
+        };
+        assert_eq!(entries(3, 2), [Position::Board(1)]);
+        assert_eq!(entries(6, 4), [Position::Board(5), Position::Board(3)]);
+        assert_eq!(entries(4, 4), [Position::Board(3)]);
+        assert!(entries(5, 1).is_empty());
+        assert!(board.bar_entry_moves(Dice::from_numbers(3, 2)).iter().all(|hm| *hm.from() == PositionCompressed::BAR));
+
+        assert!(Board::new().bar_entry_moves(Dice::from_numbers(6, 4)).is_empty());
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let mut board = Board::new();
+        let start = board.canonical_key();
+        board.switch_player();
+        let black_to_move = board.canonical_key();
+        assert_ne!(start, black_to_move);
+        // The starting position is symmetric, so the mirrored view is the same position.
+        assert_eq!(Board::new().mirrored().canonical_key(), start);
+
+        board.make_move("8/5 6/5".parse().unwrap(), Dice::from_numbers(3, 1)).unwrap();
+        let after_move = board.canonical_key();
+        assert_ne!(after_move, start);
+        board.pass_turn();
+        assert_ne!(board.canonical_key(), after_move);
+        board.pass_turn();
+        assert_eq!(board.canonical_key(), after_move);
+        assert_eq!(board.from_whites_perspective().canonical_key(), after_move);
+
+        // Without empty points the player to move is still told apart.
+        let mut points = [0; 24];
+        points[..12].fill(1);
+        points[12..].fill(-1);
+        points[0] = 4;
+        points[23] = -4;
+        let mut full = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        let white = full.canonical_key();
+        full.switch_player();
+        assert_ne!(full.canonical_key(), white);
+    }
+
+    #[test]
+    fn test_hash_set_of_boards() {
+        use std::collections::HashSet;
+
+        // Clearing a point of the passive player leaves its sign bit behind.
+        let mut stale = Board::new();
+        stale.set_passive_player_checker_on_position(0, 0);
+        stale.set_active_player_checker_on_position(1, 2);
+        let mut clean = Board::new();
+        clean.set_checkers_on_position(0, 0);
+        clean.set_checkers_on_position(1, 2);
+        assert_ne!(stale.board, clean.board);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(Board::new()));
+        assert!(!set.insert(Board::new()));
+        assert!(set.insert(stale));
+        assert!(!set.insert(clean));
+        assert!(set.insert(Board::nackgammon()));
+        assert!(set.insert(stale.mirrored()));
+        // The starting position is symmetric, only the sign bits of empty points differ.
+        assert!(!set.insert(Board::new().mirrored()));
+        assert_eq!(set.len(), 4);
+    }
+
+    #[test]
+    fn test_occupied_opening() {
+        let occupied = Board::new().occupied().collect::<Vec<_>>();
+        assert_eq!(occupied, vec![(0, 2), (5, -5), (7, -3), (11, 5), (12, -5), (16, 3), (18, 5), (23, -2)]);
+        assert_eq!(Board::empty().occupied().count(), 0);
+    }
+
+    #[test]
+    fn test_checker_positions() {
+        let opening = vec![(Position::Board(0), 2), (Position::Board(11), 5), (Position::Board(16), 3), (Position::Board(18), 5)];
+        let mut board = Board::new();
+        assert_eq!(board.checker_positions(Player::White), opening);
+        assert_eq!(board.checker_positions(Player::Black), opening);
+        board.switch_player();
+        assert_eq!(board.checker_positions(Player::White), opening);
+
+        let mut points = [0; 24];
+        points[20] = 3;
+        points[22] = -1;
+        let board = Board::from_points(points, 2, 1, 10, 13, Player::Black).unwrap();
+        assert_eq!(board.checker_positions(Player::Black), [(Position::Bar, 2), (Position::Board(20), 3), (Position::Home, 10)]);
+        assert_eq!(board.checker_positions(Player::White), [(Position::Bar, 1), (Position::Board(1), 1), (Position::Home, 13)]);
+    }
+
+    #[test]
+    fn test_checker_count() {
+        let mut board = Board::new();
+        assert_eq!(board.checker_count(Player::White), 15);
+        assert_eq!(board.checker_count(Player::Black), 15);
+        board.make_move(single_move(&[(0, 3), (0, 4)]), Dice::from_numbers(3, 4)).unwrap();
+        assert_eq!(board.checker_count(Player::White), 15);
+        assert_eq!(board.checker_count(Player::Black), 15);
+        assert_eq!(Board::empty().checker_count(Player::White), 0);
+    }
+
+    #[test]
+    fn test_random_move_is_uniform() {
+        use rand::{SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(5);
+        let board = Board::new();
+        let dice = Dice::from_numbers(3, 1);
+        let moves = board.generate_moves(dice);
+        let samples_per_move = 1000;
+
+        let mut counts = vec![0; moves.len()];
+        for _ in 0..samples_per_move * moves.len() {
+            let sample = board.random_move(dice, &mut rng).unwrap();
+            let index = moves.iter().position(|m| *m == sample).unwrap();
+            counts[index] += 1;
+        }
+        // Chi-square statistic with 18 degrees of freedom, its 99.9% quantile is about 42.3.
+        let expected = samples_per_move as f32;
+        let chi_square: f32 = counts.iter().map(|&count| (count as f32 - expected).powi(2) / expected).sum();
+        assert!(chi_square < 42.3, "{:?}", counts);
+
+        let mut points = [0; 24];
+        points[0..6].copy_from_slice(&[-2, -2, -2, -2, -2, -3]);
+        points[10] = 14;
+        let closed_out = Board::from_points(points, 1, 2, 0, 0, Player::White).unwrap();
+        assert!(closed_out.random_move(dice, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_borne_off() {
+        assert_eq!(Board::new().borne_off(Player::White), 0);
+        assert_eq!(Board::new().bear_off_progress(Player::Black), 0.0);
+
+        // White has borne off 6 checkers and Black 3, both are bearing off.
+        let mut points = [0; 24];
+        points[18..24].copy_from_slice(&[2, 2, 1, 1, 2, 1]);
+        points[0..6].copy_from_slice(&[-3, -2, -2, -2, -2, -1]);
+        let board = Board::from_points(points, 0, 0, 6, 3, Player::White).unwrap();
+        let mut switched = board;
+        switched.switch_player();
+        for board in [board, switched] {
+            assert_eq!(board.borne_off(Player::White), 6);
+            assert_eq!(board.borne_off(Player::Black), 3);
+            assert_eq!(board.bear_off_progress(Player::White), 0.4);
+            assert_eq!(board.bear_off_progress(Player::Black), 0.2);
+        }
+    }
+
+    #[test]
+    fn test_longest_prime() {
+        assert_eq!(Board::new().longest_prime(Player::White), 1);
+        assert_eq!(Board::new().longest_prime(Player::Black), 1);
+
+        // White holds its 8 to 4 points, Black the 1 and 2 points and its own 4 point.
+        let mut points = [0; 24];
+        points[16..21].copy_from_slice(&[3, 3, 3, 3, 3]);
+        points[22] = -2;
+        points[23] = -2;
+        points[3] = -11;
+        let board = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert_eq!(board.longest_prime(Player::White), 5);
+        assert_eq!(board.longest_prime(Player::Black), 2);
+        assert!(!board.is_closed_out(Player::White));
+    }
+
+    #[test]
+    fn test_is_closed_out() {
+        let mut points = [0; 24];
+        points[18..24].copy_from_slice(&[2, 2, 2, 2, 2, 3]);
+        points[5] = -2;
+        points[10] = -12;
+        let board = Board::from_points(points, 2, 1, 0, 0, Player::White).unwrap();
+        assert!(board.is_closed_out(Player::White));
+        assert!(!board.is_closed_out(Player::Black));
+        assert_eq!(board.longest_prime(Player::White), 6);
+
+        let mut switched = board;
+        switched.switch_player();
+        assert!(switched.is_closed_out(Player::White));
+
+        let mut points_open = points;
+        points_open[20] = 1;
+        points_open[23] = 4;
+        let board = Board::from_points(points_open, 2, 1, 0, 0, Player::White).unwrap();
+        assert!(!board.is_closed_out(Player::White));
+    }
+
+    #[test]
+    fn test_new_with_player() {
+        assert_eq!(Board::new_with_player(Player::White), Board::new());
+        let black = Board::new_with_player(Player::Black);
+        assert_eq!(black.active_player(), Player::Black);
+        assert_eq!(black.validate(), Ok(()));
+        assert!(black.from_whites_perspective().occupied().eq(Board::new().occupied()));
+
+        for dice in Dice::ALL {
+            let white_moves = Board::new().generate_moves(dice);
+            assert_eq!(black.generate_moves(dice), white_moves);
+            // The same move leaves the position with the colors swapped.
+            for m in white_moves {
+                let mut white_after = Board::new();
+                white_after.make_move(m, dice).unwrap();
+                let mut black_after = black;
+                black_after.make_move(m, dice).unwrap();
+                white_after.set_active_player(Player::White);
+                assert_eq!(black_after, white_after);
+            }
+        }
+    }
+
+    #[test]
+    fn test_entry_chance() {
+        let board = Board::new();
+        assert_eq!(board.blocked_entry_points(), 1);
+        assert_eq!(board.entry_chance(), 35.0 / 36.0);
+
+        let mut points = [0; 24];
+        points[10] = 15;
+        points[12] = -15;
+        let open = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert_eq!(open.blocked_entry_points(), 0);
+        assert_eq!(open.entry_chance(), 1.0);
+
+        // Black holds all six points of its home board.
+        let mut points = [0; 24];
+        points[..6].copy_from_slice(&[-2, -2, -2, -2, -2, -5]);
+        points[12] = 14;
+        let closed = Board::from_points(points, 1, 0, 0, 0, Player::White).unwrap();
+        assert_eq!(closed.blocked_entry_points(), 6);
+        assert_eq!(closed.entry_chance(), 0.0);
+        assert!(closed.is_closed_out(Player::Black));
+    }
+
+    #[test]
+    fn test_hit_probability_direct_shot() {
+        // A blot six pips in front of a single shooter, all points in between are blocked.
+        let mut points = [0; 24];
+        points[10] = 1;
+        points[11..16].copy_from_slice(&[2, 2, 2, 2, 2]);
+        points[20] = 4;
+        points[16] = -1;
+        points[0] = -14;
+        let board = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert!((board.hit_probability(10) - 11.0 / 36.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hit_probability_indirect_shot() {
+        // Eight pips away only 6-2, 5-3, 4-4 and 2-2 hit.
+        let mut points = [0; 24];
+        points[10] = 1;
+        points[20] = 14;
+        points[18] = -1;
+        points[0] = -14;
+        let board = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert!((board.hit_probability(10) - 6.0 / 36.0).abs() < 1e-6);
+
+        // Blocking the landing points of 6-2 and 2-2 leaves 5-3 and 4-4.
+        points[20] = 10;
+        points[12] = 2;
+        points[16] = 2;
+        let board = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert!((board.hit_probability(10) - 3.0 / 36.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hit_probability_unavoidable_shot() {
+        // Shooters one to six pips away, every roll hits.
+        let mut points = [0; 24];
+        points[10] = 1;
+        points[20] = 14;
+        points[11..17].copy_from_slice(&[-1; 6]);
+        points[0] = -9;
+        let board = Board::from_points(points, 0, 0, 0, 0, Player::White).unwrap();
+        assert_eq!(board.hit_probability(10), 1.0);
+    }
+
+    #[test]
+    fn test_total_blot_exposure() {
+        // Blots six and four pips in front of a single shooter. 6-4 hits either but not both.
+        let mut points = [0; 24];
+        points[12] = 1;
+        points[20] = 13;
+        points[18] = -1;
+        points[0] = -14;
+        let mut board = Board::from_points(points, 0, 0, 1, 0, Player::White).unwrap();
+        let single = board.hit_probability(12) * 13.0;
+        assert!((board.total_blot_exposure() - single).abs() < 1e-6);
+
+        board.set_active_player_checker_on_position(14, 1);
+        board.set_active_home(0);
+        assert_eq!(board.blots(Player::White).collect::<Vec<_>>(), [12, 14]);
+        assert_eq!(board.blots(Player::Black).collect::<Vec<_>>(), [18]);
+        let individual = [board.hit_probability(12) * 13.0, board.hit_probability(14) * 15.0];
+        let exposure = board.total_blot_exposure();
+        assert!(exposure > individual[0].max(individual[1]));
+        // 6-4 is counted for the more expensive blot only.
+        assert!((exposure - (individual[0] + individual[1] - 2.0 / 36.0 * 13.0)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(Board::new().outcome(), GameOutcome::Ongoing);
+
+        let mut board = Board::empty();
+        board.set_active_home(15);
+        board.set_passive_home(3);
+        board.set_passive_player_checker_on_position(3, 12);
+        assert_eq!(board.outcome(), GameOutcome::Win(Player::White));
+        board.switch_player();
+        assert_eq!(board.outcome(), GameOutcome::Win(Player::White));
+
+        let mut board = Board::empty();
+        board.set_active_home(15);
+        board.set_passive_player_checker_on_position(3, 15);
+        assert_eq!(board.outcome(), GameOutcome::Gammon(Player::White));
+        board.switch_player();
+        assert_eq!(board.outcome(), GameOutcome::Gammon(Player::White));
+
+        let mut board = Board::empty();
+        board.set_active_home(15);
+        board.set_passive_player_checker_on_position(3, 14);
+        board.set_passive_player_checker_on_position(20, 1);
+        assert_eq!(board.outcome(), GameOutcome::Backgammon(Player::White));
+        board.switch_player();
+        assert_eq!(board.outcome(), GameOutcome::Backgammon(Player::White));
+
+        let mut board = Board::empty();
+        board.set_active_home(15);
+        board.set_passive_player_checker_on_position(3, 14);
+        board.set_passive_bar(1);
+        assert_eq!(board.outcome(), GameOutcome::Backgammon(Player::White));
+    }
+
+    #[test]
+    fn test_bear_off_larger_die_uses_highest_point() {
+        // Checkers on the 5-point (index 19) and the 4-point (index 20), rolled 6-5.
+        let board = bear_off_board(&[(19, 2), (20, 2)]);
+        let half_moves = board.generate_half_moves(Dice::from_numbers(6, 5));
+        assert_eq!(half_moves.len(), 2);
+        for (half_move, _) in half_moves.iter() {
+            assert_eq!(*half_move.from(), PositionCompressed::from_index(19));
+            assert_eq!(*half_move.to(), PositionCompressed::HOME);
+        }
+
+        let moves = board.generate_moves(Dice::from_numbers(6, 5));
+        assert_eq!(moves.len(), 1);
+        assert!(moves[0].iter().all(|hm| *hm.from() == PositionCompressed::from_index(19)));
+    }
+
+    #[test]
+    fn test_bear_off_larger_die_after_highest_point_cleared() {
+        // A single checker on the 5-point: after it is borne off, the 4-point becomes the highest.
+        let board = bear_off_board(&[(19, 1), (20, 2)]);
+        let moves = board.generate_moves(Dice::from_numbers(6, 5));
+        assert_eq!(moves.len(), 1);
+        let from = moves[0].iter().map(|hm| *hm.from()).collect::<Vec<_>>();
+        assert!(from.contains(&PositionCompressed::from_index(19)));
+        assert!(from.contains(&PositionCompressed::from_index(20)));
+        assert!(moves[0].iter().all(|hm| *hm.to() == PositionCompressed::HOME));
+    }
+
+    #[test]
+    fn test_bear_off_larger_die_blocked_by_higher_point() {
+        // A checker on the 6-point prevents bearing off the 2-point checker with a 5.
+        let board = bear_off_board(&[(18, 1), (22, 1)]);
+        let half_moves = board.generate_half_moves(Dice::from_numbers(5, 5));
+        assert_eq!(half_moves.len(), 1);
+        assert_eq!(*half_moves[0].0.from(), PositionCompressed::from_index(18));
+        assert_eq!(*half_moves[0].0.to(), PositionCompressed::from_index(23));
+    }
+
+    #[test]
+    fn test_make_halfmove_unchecked_uses_the_right_points() {
+        // PositionCompressed stores board positions as index + 3.
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(5, 2);
+        board.set_passive_player_checker_on_position(8, 1);
+        board.make_halfmove_unchecked(&HalfMove::from_compressed(PositionCompressed::from_index(5), PositionCompressed::from_index(8)));
+        assert_eq!(board.get_checkers_on_position(5), 1);
+        assert_eq!(board.get_checkers_on_position(8), 1);
+        assert_eq!(board.get_checkers_on_position(4), 0);
+        assert_eq!(board.get_checkers_on_position(7), 0);
+        assert_eq!(board.get_passive_bar(), 1);
+    }
+
+    #[test]
+    fn test_to_fancy_string_opening_position() {
+        let expected = "\
+12  11  10   9   8   7  | W  |   6   5   4   3   2   1
+ 5   0   0   0  -3   0  |  0 |  -5   0   0   0   0   2
+==========================================================
+-5   0   0   0   3   0  |  0 |    5   0   0   0   0  -2
+13  14  15  16  17  18  | B  |   19  20  21  22  23  24
+Borne off: W 0 | B 0";
+        let mut board = Board::new();
+        assert_eq!(board.to_fancy_string(), expected);
+        board.switch_player();
+        assert_eq!(board.to_fancy_string(), expected);
+    }
+
+    #[test]
+    fn test_to_fancy_string_aligns_two_digit_bar() {
+        let mut points = [0; 24];
+        points[20] = 3;
+        points[3] = -5;
+        let board = Board::from_points(points, 12, 10, 0, 0, Player::White).unwrap();
+        let lines = board.to_fancy_string().lines().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(lines[1], " 0   0   0   0   0   0  | 12 |   0   0  -5   0   0   0");
+        for line in [&lines[1], &lines[3], &lines[4]] {
+            assert_eq!(line.find('|'), lines[0].find('|'));
+            assert_eq!(line.rfind('|'), lines[0].rfind('|'));
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use std::{fmt::Debug, mem::MaybeUninit, num::NonZeroU8};
-use rand::random_range;
+use core::{fmt::{Debug, Display}, mem::MaybeUninit, num::NonZeroU8};
+#[cfg(feature = "std")]
+use core::str::FromStr;
+use rand::Rng;
 
-use crate::{backgammon::dice, misc::TinyVec};
+use crate::misc::TinyVec;
 
 
 /// Represents the dice used in the game of Backgammon. Stores the values of two die and their usage state in 1 byte.
@@ -14,12 +16,14 @@ use crate::{backgammon::dice, misc::TinyVec};
 /// 
 /// Else A is 0 if die1 has not been used, B is 0 if die2 has not been used otherwise A and/or B are 1
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "SerializedDice", try_from = "SerializedDice"))]
 pub struct Dice {
     data: NonZeroU8
 }
 
 impl Dice {
-    pub const ALL: [Self; 20] = [
+    pub const ALL: [Self; 21] = [
         Dice::from_numbers(1, 1), Dice::from_numbers(1, 2), Dice::from_numbers(1, 3),
         Dice::from_numbers(1, 4), Dice::from_numbers(1, 5), Dice::from_numbers(1, 6),
         Dice::from_numbers(2, 2), Dice::from_numbers(2, 3), Dice::from_numbers(2, 4),
@@ -27,13 +31,44 @@ impl Dice {
         Dice::from_numbers(3, 3), Dice::from_numbers(3, 4), Dice::from_numbers(3, 5),
         Dice::from_numbers(3, 6), 
         Dice::from_numbers(4, 4), Dice::from_numbers(4, 5), Dice::from_numbers(4, 6),
-        Dice::from_numbers(5, 5), 
+        Dice::from_numbers(5, 5), Dice::from_numbers(5, 6),
         Dice::from_numbers(6, 6)
     ];
 
+    pub const ALL_WITH_PROBABILITY: [(Self, f32); 21] = [
+        (Dice::from_numbers(1, 1), 1.0 / 36.0), (Dice::from_numbers(1, 2), 2.0 / 36.0), (Dice::from_numbers(1, 3), 2.0 / 36.0),
+        (Dice::from_numbers(1, 4), 2.0 / 36.0), (Dice::from_numbers(1, 5), 2.0 / 36.0), (Dice::from_numbers(1, 6), 2.0 / 36.0),
+        (Dice::from_numbers(2, 2), 1.0 / 36.0), (Dice::from_numbers(2, 3), 2.0 / 36.0), (Dice::from_numbers(2, 4), 2.0 / 36.0),
+        (Dice::from_numbers(2, 5), 2.0 / 36.0), (Dice::from_numbers(2, 6), 2.0 / 36.0),
+        (Dice::from_numbers(3, 3), 1.0 / 36.0), (Dice::from_numbers(3, 4), 2.0 / 36.0), (Dice::from_numbers(3, 5), 2.0 / 36.0),
+        (Dice::from_numbers(3, 6), 2.0 / 36.0),
+        (Dice::from_numbers(4, 4), 1.0 / 36.0), (Dice::from_numbers(4, 5), 2.0 / 36.0), (Dice::from_numbers(4, 6), 2.0 / 36.0),
+        (Dice::from_numbers(5, 5), 1.0 / 36.0), (Dice::from_numbers(5, 6), 2.0 / 36.0),
+        (Dice::from_numbers(6, 6), 1.0 / 36.0)
+    ];
+
+    /// All 36 ordered rolls, `(first, second)` in row-major order from `1/1` to `6/6`.
+    /// Non-doubles appear twice, once for each order of the dice.
+    pub const ALL_ORDERED: [Self; 36] = {
+        let mut all = [Dice::from_ordered(1, 1); 36];
+        let mut i = 0;
+        while i < 36 {
+            all[i] = Dice::from_ordered(i as u8 / 6 + 1, i as u8 % 6 + 1);
+            i += 1;
+        }
+        all
+    };
+
+    #[cfg(feature = "std")]
     pub fn roll() -> Self {
-        let die1 = random_range(1..=6);
-        let die2 = random_range(1..=6);
+        Self::roll_with(&mut rand::rng())
+    }
+
+    /// Rolls the dice with the given random number generator, e.g. a seeded `StdRng`
+    /// to reproduce a sequence of rolls.
+    pub fn roll_with<R: Rng>(rng: &mut R) -> Self {
+        let die1 = rng.random_range(1..=6);
+        let die2 = rng.random_range(1..=6);
         Dice::from_numbers(die1, die2)
     }
 
@@ -51,6 +86,28 @@ impl Dice {
         dice
     }
 
+    /// The values of the dice as `(smaller, larger)`, ignoring their order and usage.
+    pub fn canonical(&self) -> (u8, u8) {
+        // die2 is not valid for fully used doubles.
+        let (die1, die2) = if self.is_double() { (self.die1(), self.die1()) } else { (self.die1(), self.die2()) };
+        (die1.min(die2), die1.max(die2))
+    }
+
+    /// Probability of rolling this combination, ignoring the order of the dice.
+    pub fn probability(&self) -> f32 {
+        if self.is_double() {
+            1.0 / 36.0
+        } else {
+            2.0 / 36.0
+        }
+    }
+
+    /// Dice rolled as `first` then `second`. `first` becomes [`die1`](Self::die1), so it is
+    /// listed first by [`availiable_dice`](Self::availiable_dice).
+    pub const fn from_ordered(first: u8, second: u8) -> Self {
+        Self::from_numbers(first, second)
+    }
+
     pub const fn from_numbers(die1: u8, die2: u8) -> Self {
         Dice { data: NonZeroU8::new((die2 << 3) | die1).unwrap() }
     }
@@ -118,6 +175,8 @@ impl Dice {
         self.die1() == self.die2() || self.data.get() & 0b11111000 == 0b11111000
     }
 
+    /// The die values that can still be played, `die1` before `die2`. Move generation tries
+    /// the dice in this order, so it determines which die is consumed first.
     pub fn availiable_dice(&self) -> TinyVec<u8, 2> {
         if self.is_double() {
             if self.die_is_used_double() {
@@ -136,10 +195,32 @@ impl Dice {
             vec
         }
     }
+
+    /// How many dice have been played: 0 to 2 for non-doubles, 0 to 4 for doubles.
+    pub fn used_count(&self) -> u8 {
+        if self.is_double() {
+            if self.die_is_used_double() {
+                4
+            } else {
+                self.data.get() >> 6
+            }
+        } else {
+            self.die_1_is_used_non_double() as u8 + self.die_2_is_used_non_double() as u8
+        }
+    }
+
+    /// Sum of the pips of the dice that can still be played.
+    pub fn remaining_pips(&self) -> u8 {
+        if self.is_double() {
+            self.die1() * (4 - self.used_count())
+        } else {
+            self.availiable_dice().iter().sum()
+        }
+    }
 }
 
 impl Debug for Dice {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if self.is_double() {
             write!(f, "Double({}): ", self.die1())?;
             if self.die_is_used_double() {
@@ -165,10 +246,172 @@ impl Debug for Dice {
     }
 }
 
+/// Orders dice by their [`canonical`](Dice::canonical) values, so `3/5` and `5/3` are adjacent.
+/// To stay consistent with [`Eq`], dice with the same values are further ordered by
+/// the order they were rolled in and their usage state.
+impl Ord for Dice {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.canonical().cmp(&other.canonical())
+            .then_with(|| self.data.cmp(&other.data))
+    }
+}
+
+impl PartialOrd for Dice {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Dice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // die2 is not valid for fully used doubles, so doubles print die1 twice.
+        if self.is_double() {
+            write!(f, "{}/{}", self.die1(), self.die1())
+        } else {
+            write!(f, "{}/{}", self.die1(), self.die2())
+        }
+    }
+}
+
+/// Error returned when parsing a [`Dice`] from a string like `"3/5"` fails.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseDiceError {
+    /// The string is not of the form `"<die>/<die>"`.
+    Malformed(String),
+    /// A die value is not in the range 1..=6.
+    OutOfRange(u8),
+}
+
+#[cfg(feature = "std")]
+impl Display for ParseDiceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseDiceError::Malformed(s) => write!(f, "malformed dice \"{}\", expected \"<die>/<die>\"", s),
+            ParseDiceError::OutOfRange(value) => write!(f, "die value {} is not in the range 1..=6", value),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for ParseDiceError {}
+
+#[cfg(feature = "std")]
+impl FromStr for Dice {
+    type Err = ParseDiceError;
+
+    /// Parses dice in the format produced by [`Display`], e.g. `"3/5"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || ParseDiceError::Malformed(s.to_string());
+        let (die1, die2) = s.trim().split_once('/').ok_or_else(malformed)?;
+        let die1 = die1.trim().parse::<u8>().map_err(|_| malformed())?;
+        let die2 = die2.trim().parse::<u8>().map_err(|_| malformed())?;
+        for die in [die1, die2] {
+            if !(1..=6).contains(&die) {
+                return Err(ParseDiceError::OutOfRange(die));
+            }
+        }
+        Ok(Dice::from_numbers(die1, die2))
+    }
+}
+
+/// Packed form of [`Dice`] used for serialization. Deserialization checks that the byte
+/// is a valid layout before it becomes a [`Dice`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedDice {
+    data: u8,
+}
+
+#[cfg(feature = "serde")]
+impl From<Dice> for SerializedDice {
+    fn from(dice: Dice) -> Self {
+        SerializedDice { data: dice.data.get() }
+    }
+}
+
+/// Error returned when deserializing a [`Dice`] from a byte that is not a valid layout.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+struct InvalidDice(u8);
+
+#[cfg(feature = "serde")]
+impl Display for InvalidDice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#010b} is not a valid dice layout", self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedDice> for Dice {
+    type Error = InvalidDice;
+
+    fn try_from(serialized: SerializedDice) -> Result<Self, Self::Error> {
+        let data = serialized.data;
+        let die1 = data & 0x7;
+        let die2 = (data >> 3) & 0x7;
+        // Fully used doubles store 0b11111 above die1 instead of die2 and the usage state.
+        let fully_used_double = data & 0b11111000 == 0b11111000;
+        if (1..=6).contains(&die1) && (fully_used_double || (1..=6).contains(&die2)) {
+            Ok(Dice { data: NonZeroU8::new(data).unwrap() })
+        } else {
+            Err(InvalidDice(data))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
     use super::*;
 
+    #[test]
+    fn test_ord_sorts_by_canonical_values() {
+        // Sorted order: 1/1, 1/2, ..., 1/6, 2/2, 2/3, ..., 6/6.
+        let mut shuffled = Dice::ALL;
+        shuffled.shuffle(&mut StdRng::seed_from_u64(1));
+        shuffled.sort();
+        assert_eq!(shuffled, Dice::ALL);
+
+        assert_eq!(Dice::from_numbers(5, 3).canonical(), (3, 5));
+        assert_eq!(Dice::from_numbers(5, 3).canonical(), Dice::from_numbers(3, 5).canonical());
+        assert!(Dice::from_numbers(5, 3) < Dice::from_numbers(4, 4));
+        assert!(Dice::from_numbers(2, 6) < Dice::from_numbers(3, 3));
+        assert_eq!(Dice::from_numbers(4, 4).use_die(4).canonical(), (4, 4));
+    }
+
+    const _: () = assert!(Dice::ALL.len() == 21);
+
+    #[test]
+    fn test_all_contains_every_roll() {
+        let canonical: Vec<(u8, u8)> = Dice::ALL.iter().map(Dice::canonical).collect();
+        let expected: Vec<(u8, u8)> = (1..=6).flat_map(|i| (i..=6).map(move |j| (i, j))).collect();
+        assert_eq!(canonical, expected);
+        assert!(Dice::ALL.iter().zip(Dice::ALL_WITH_PROBABILITY).all(|(&a, (b, _))| a == b));
+    }
+
+    #[test]
+    fn test_all_ordered_multiplicities() {
+        assert_eq!(Dice::ALL_ORDERED[2], Dice::from_ordered(1, 3));
+        assert_eq!(Dice::ALL_ORDERED[12].die1(), 3);
+        for (dice, probability) in Dice::ALL_WITH_PROBABILITY {
+            let count = Dice::ALL_ORDERED.iter().filter(|d| d.canonical() == dice.canonical()).count();
+            assert_eq!(count, if dice.is_double() { 1 } else { 2 });
+            assert_eq!(count as f32 / 36.0, probability);
+        }
+    }
+
+    #[test]
+    fn test_roll_with_seed_is_reproducible() {
+        let rolls = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..100).map(|_| Dice::roll_with(&mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(42), rolls(42));
+        assert_ne!(rolls(42), rolls(43));
+    }
+
     #[test]
     fn test_dice() {
         for i in 1..=6 {
@@ -205,6 +448,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_used_count_and_remaining_pips() {
+        let mut dice = Dice::from_numbers(5, 5);
+        for used in 0..4 {
+            assert_eq!(dice.used_count(), used);
+            assert_eq!(dice.remaining_pips(), 5 * (4 - used));
+            dice.use_double();
+        }
+        assert_eq!(dice.used_count(), 4);
+        assert_eq!(dice.remaining_pips(), 0);
+
+        let mut dice = Dice::from_numbers(3, 6);
+        assert_eq!((dice.used_count(), dice.remaining_pips()), (0, 9));
+        dice.use_die2();
+        assert_eq!((dice.used_count(), dice.remaining_pips()), (1, 3));
+        dice.use_die1();
+        assert_eq!((dice.used_count(), dice.remaining_pips()), (2, 0));
+    }
+
     #[test]
     fn test_dice_roll() {
         for _ in 1..=100 {
@@ -218,5 +480,58 @@ mod tests {
             }
         }
     }
-}
 
+    #[test]
+    fn test_dice_display_from_str_round_trip() {
+        for i in 1..=6 {
+            for j in i..=6 {
+                let dice = Dice::from_numbers(i, j);
+                let s = dice.to_string();
+                assert_eq!(s, format!("{}/{}", i, j));
+                assert_eq!(s.parse::<Dice>(), Ok(dice));
+            }
+        }
+    }
+
+    #[test]
+    fn test_dice_display_used_double() {
+        let mut dice = Dice::from_numbers(5, 5);
+        for _ in 0..4 {
+            dice.use_double();
+        }
+        assert_eq!(dice.to_string(), "5/5");
+    }
+
+    #[test]
+    fn test_dice_from_str_errors() {
+        assert_eq!("0/3".parse::<Dice>(), Err(ParseDiceError::OutOfRange(0)));
+        assert_eq!("3/7".parse::<Dice>(), Err(ParseDiceError::OutOfRange(7)));
+        for s in ["", "3", "3/", "/5", "3-5", "a/b", "3/5/1"] {
+            assert_eq!(s.parse::<Dice>(), Err(ParseDiceError::Malformed(s.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_dice_probability() {
+        let sum = Dice::ALL_WITH_PROBABILITY.iter().map(|(_, p)| p).sum::<f32>();
+        assert!((sum - 1.0).abs() < 1e-6);
+        for (dice, probability) in Dice::ALL_WITH_PROBABILITY {
+            assert_eq!(dice.probability(), probability);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid_layouts() {
+        let mut used = Dice::from_numbers(4, 4);
+        (0..4).for_each(|_| used.use_double());
+        for dice in [Dice::from_numbers(3, 5), Dice::from_numbers(4, 4).use_die(4), used] {
+            let json = serde_json::to_string(&dice).unwrap();
+            assert_eq!(serde_json::from_str::<Dice>(&json).unwrap(), dice);
+        }
+        for data in [0u8, 0b00001111, 0b00111001, 0b00000110] {
+            let json = format!("{{\"data\":{data}}}");
+            assert!(serde_json::from_str::<Dice>(&json).is_err());
+        }
+    }
+}
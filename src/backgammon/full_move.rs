@@ -1,6 +1,22 @@
-use std::{fmt::Debug, ops::Deref};
+use core::{fmt::{Debug, Display}, hash::{Hash, Hasher}, ops::Deref};
+#[cfg(feature = "std")]
+use core::str::FromStr;
 
 use crate::{backgammon::HalfMove, misc::TinyVec};
+#[cfg(feature = "std")]
+use crate::backgammon::{Board, Position, PositionCompressed};
+
+/// Error returned by [`Move::from_half_moves`] when given more than four half moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyHalfMoves;
+
+impl Display for TooManyHalfMoves {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a move consists of at most four half moves")
+    }
+}
+
+impl core::error::Error for TooManyHalfMoves {}
 
 #[derive(Clone, Copy)]
 pub struct Move {
@@ -14,20 +30,211 @@ impl Move {
         }
     }
 
+    /// Collects the half moves in order. Unlike repeated [`Self::add_half_move`] calls this
+    /// returns an error instead of panicking if there are more than four.
+    pub fn from_half_moves(half_moves: impl IntoIterator<Item = HalfMove>) -> Result<Move, TooManyHalfMoves> {
+        let mut m = Move::new();
+        for half_move in half_moves {
+            if m.half_moves.len() == 4 {
+                return Err(TooManyHalfMoves);
+            }
+            m.add_half_move(half_move);
+        }
+        Ok(m)
+    }
+
     pub fn add_half_move(&mut self, half_move: HalfMove) {
         self.half_moves.push(half_move);
     }
 
     pub fn unordered_equal(&self, other: &Self) -> bool {
+        if self.half_moves.len() != other.half_moves.len() {
+            return false;
+        }
+        // Each half move of `other` may only be matched once, `used` marks the matched slots.
         let mut used: u8 = 0;
         for half_move in self.half_moves.iter() {
-            match other.half_moves.iter().enumerate().position(|(i,&hm)| hm == *half_move && !used & (1 << i) != 0) {
+            match other.half_moves.iter().enumerate().position(|(i,&hm)| hm == *half_move && used & (1 << i) == 0) {
                 Some(index) => used |= 1 << index,
                 None => return false,
             }       
         }
         true 
     }
+
+    /// Key that is equal for two moves exactly if they are [`unordered_equal`](Self::unordered_equal)
+    /// and have the same length, used to deduplicate moves in a hash set.
+    pub(crate) fn unordered_key(&self) -> u64 {
+        let mut codes = [0u16; 4];
+        for (code, half_move) in codes.iter_mut().zip(self.half_moves.iter()) {
+            *code = (half_move.from().get() as u16) << 8 | half_move.to().get() as u16;
+        }
+        codes.sort_unstable();
+        codes.iter().fold(0, |key, &code| key << 16 | code as u64)
+    }
+}
+
+/// Moves are equal if they consist of the same half moves in any order, so `8/5 6/5 == 6/5 8/5`.
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        self.unordered_equal(other)
+    }
+}
+
+impl Eq for Move {}
+
+impl Hash for Move {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.unordered_key().hash(state);
+    }
+}
+
+impl Move {
+    /// Formats the move in standard notation like [`Display`], additionally marking
+    /// hits with `*` by replaying the move on `board`.
+    #[cfg(feature = "std")]
+    pub fn to_notation(&self, board: &Board) -> String {
+        let mut board = *board;
+        let hits = self.half_moves.iter()
+            .map(|half_move| {
+                let hit = match Position::from(*half_move.to()) {
+                    Position::Board(index) => board.get_checkers_on_position(index) == -1,
+                    _ => false,
+                };
+                board.make_halfmove_unchecked(half_move);
+                hit
+            })
+            .collect::<Vec<_>>();
+        self.format_notation(&hits)
+    }
+
+    /// Groups identical half moves and renders them as e.g. `"24/18 6/4(2) 6/1*"`.
+    #[cfg(feature = "std")]
+    fn format_notation(&self, hits: &[bool]) -> String {
+        let mut groups: Vec<(HalfMove, bool, u8)> = Vec::new();
+        for (i, &half_move) in self.half_moves.iter().enumerate() {
+            let hit = hits.get(i).copied().unwrap_or(false);
+            match groups.iter_mut().find(|(hm, _, _)| *hm == half_move) {
+                Some((_, group_hit, count)) => {
+                    *group_hit |= hit;
+                    *count += 1;
+                }
+                None => groups.push((half_move, hit, 1)),
+            }
+        }
+
+        let point = |position: Position| match position {
+            Position::Bar => "bar".to_string(),
+            Position::Home => "off".to_string(),
+            Position::Board(index) => (24 - index).to_string(),
+        };
+
+        groups.iter()
+            .map(|(half_move, hit, count)| {
+                let mut s = format!("{}/{}", point(Position::from(*half_move.from())), point(Position::from(*half_move.to())));
+                if *hit {
+                    s.push('*');
+                }
+                if *count > 1 {
+                    s.push_str(&format!("({})", count));
+                }
+                s
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Display for Move {
+    /// Formats the move in standard notation, e.g. `"24/18 13/11"`, `"bar/20"` or `"6/off"`.
+    /// Point numbers are from the perspective of the moving player. Hits can only be
+    /// marked with a board, see [`Move::to_notation`].
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.format_notation(&[]))
+    }
+}
+
+/// Error returned when parsing a [`Move`] from standard notation fails.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoveError {
+    /// A token is not of the form `"<from>/<to>"` with an optional `*` and `(<count>)`.
+    Malformed(String),
+    /// A point is not `bar`, `off` or a number in the range 1..=24, or is used in the wrong place.
+    InvalidPoint(String),
+    /// The notation describes more than four half moves.
+    TooManyHalfMoves,
+}
+
+#[cfg(feature = "std")]
+impl Display for ParseMoveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseMoveError::Malformed(s) => write!(f, "malformed move \"{}\", expected e.g. \"24/18 13/11\"", s),
+            ParseMoveError::InvalidPoint(s) => write!(f, "invalid point \"{}\"", s),
+            ParseMoveError::TooManyHalfMoves => write!(f, "a move consists of at most four half moves"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for ParseMoveError {}
+
+#[cfg(feature = "std")]
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    /// Parses standard notation like `"24/18 13/11"`, `"bar/20"`, `"6/off"`, `"6/4(2)"`
+    /// or chained hops like `"24/18/13"`. Hit markers (`*`) are ignored since hits
+    /// follow from the board.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut m = Move::new();
+        for token in s.split_whitespace() {
+            let (hops, count) = match token.strip_suffix(')').and_then(|t| t.split_once('(')) {
+                Some((hops, count)) => {
+                    let count = count.parse::<u8>().map_err(|_| ParseMoveError::Malformed(token.to_string()))?;
+                    (hops, count)
+                }
+                None => (token, 1),
+            };
+
+            let points = hops.split('/')
+                .map(|point| parse_point(point.trim_end_matches('*')))
+                .collect::<Result<Vec<_>, _>>()?;
+            if points.len() < 2 || count == 0 {
+                return Err(ParseMoveError::Malformed(token.to_string()));
+            }
+            if points[1..].contains(&PositionCompressed::BAR) {
+                return Err(ParseMoveError::InvalidPoint("bar".to_string()));
+            }
+            if points[..points.len() - 1].contains(&PositionCompressed::HOME) {
+                return Err(ParseMoveError::InvalidPoint("off".to_string()));
+            }
+
+            for _ in 0..count {
+                for hop in points.windows(2) {
+                    if m.len() >= 4 {
+                        return Err(ParseMoveError::TooManyHalfMoves);
+                    }
+                    m.add_half_move(HalfMove::from_compressed(hop[0], hop[1]));
+                }
+            }
+        }
+        Ok(m)
+    }
+}
+
+#[cfg(feature = "std")]
+fn parse_point(point: &str) -> Result<PositionCompressed, ParseMoveError> {
+    match point {
+        "bar" => Ok(PositionCompressed::BAR),
+        "off" => Ok(PositionCompressed::HOME),
+        _ => match point.parse::<u8>() {
+            Ok(n @ 1..=24) => Ok(PositionCompressed::from_index(24 - n)),
+            _ => Err(ParseMoveError::InvalidPoint(point.to_string())),
+        },
+    }
 }
 
 impl Deref for Move {
@@ -39,7 +246,7 @@ impl Deref for Move {
 }
 
 impl Debug for Move {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Move: ")?;
         for (i, half_move) in self.half_moves.iter().enumerate() {
             if i > 0 {
@@ -49,4 +256,162 @@ impl Debug for Move {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Move {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.half_moves.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Move {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let half_moves = Vec::<HalfMove>::deserialize(deserializer)?;
+        if half_moves.len() > 4 {
+            return Err(serde::de::Error::invalid_length(half_moves.len(), &"at most 4 half moves"));
+        }
+        let mut m = Move::new();
+        for half_move in half_moves {
+            m.add_half_move(half_move);
+        }
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn half_move(from: u8, to: u8) -> HalfMove {
+        HalfMove::from_compressed(PositionCompressed::from_index(from), PositionCompressed::from_index(to))
+    }
+
+    #[test]
+    fn test_from_half_moves() {
+        let double = [half_move(0, 4), half_move(0, 4), half_move(11, 15), half_move(16, 20)];
+        let m = Move::from_half_moves(double).unwrap();
+        assert_eq!(m.len(), 4);
+        assert!(m.iter().eq(double.iter()));
+        assert_eq!(Move::from_half_moves([]), Ok(Move::new()));
+        assert_eq!(Move::from_half_moves(double.into_iter().chain([half_move(18, 22)])), Err(TooManyHalfMoves));
+    }
+
+    #[test]
+    fn test_display_opening_move() {
+        let mut m = Move::new();
+        m.add_half_move(half_move(0, 6));
+        m.add_half_move(half_move(11, 13));
+        assert_eq!(m.to_string(), "24/18 13/11");
+        assert_eq!(m.to_notation(&Board::new()), "24/18 13/11");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let m: Move = "bar/20 13/11 6/off".parse().unwrap();
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap().to_string(), "bar/20 13/11 6/off");
+        let too_long = format!("[{}]", [r#"{"from":"Bar","to":{"Board":4}}"#; 5].join(","));
+        assert!(serde_json::from_str::<Move>(&too_long).is_err());
+    }
+
+    #[test]
+    fn test_eq_ignores_half_move_order() {
+        use core::hash::BuildHasher;
+
+        let parse = |s: &str| s.parse::<Move>().unwrap();
+        let state = std::collections::hash_map::RandomState::new();
+        let pairs = [("8/5 6/5", "6/5 8/5"), ("24/20 13/9 6/2", "6/2 24/20 13/9"), ("bar/22 22/16", "bar/22 22/16")];
+        for (a, b) in pairs {
+            assert_eq!(parse(a), parse(b));
+            assert_eq!(state.hash_one(parse(a)), state.hash_one(parse(b)));
+        }
+        assert_ne!(parse("8/5 6/5"), parse("8/5 6/4"));
+        assert_ne!(parse("8/5"), parse("8/5 8/5"));
+        assert_ne!(parse("24/18"), parse("24/18/13"));
+
+        let moves: std::collections::HashSet<Move> = ["8/5 6/5", "6/5 8/5", "13/10 24/23"].map(parse).into_iter().collect();
+        assert_eq!(moves.len(), 2);
+    }
+
+    #[test]
+    fn test_unordered_equal_with_duplicate_half_moves() {
+        let parse = |s: &str| s.parse::<Move>().unwrap();
+        assert!(parse("6/5 6/5").unordered_equal(&parse("6/5 6/5")));
+        assert!(parse("8/5 6/5").unordered_equal(&parse("6/5 8/5")));
+        assert!(parse("6/5(2) 8/5").unordered_equal(&parse("6/5 8/5 6/5")));
+        assert!(!parse("6/5 6/5").unordered_equal(&parse("6/5 8/5")));
+        assert!(!parse("6/5 8/5").unordered_equal(&parse("6/5 6/5")));
+        // A prefix is not the same move, even if every half move can be matched.
+        assert!(!parse("6/5").unordered_equal(&parse("6/5 6/5")));
+        assert!(!parse("6/5 6/5").unordered_equal(&parse("6/5 6/5 6/5")));
+        assert!(!parse("6/5 6/5 6/5").unordered_equal(&parse("6/5 6/5")));
+    }
+
+    #[test]
+    fn test_display_bar_and_bear_off() {
+        let mut m = Move::new();
+        m.add_half_move(HalfMove::from_compressed(PositionCompressed::BAR, PositionCompressed::from_index(4)));
+        m.add_half_move(HalfMove::from_compressed(PositionCompressed::from_index(18), PositionCompressed::HOME));
+        assert_eq!(m.to_string(), "bar/20 6/off");
+    }
+
+    #[test]
+    fn test_notation_double_hit() {
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(0, 1);
+        board.set_active_player_checker_on_position(5, 1);
+        board.set_passive_player_checker_on_position(3, 1);
+        board.set_passive_player_checker_on_position(7, 1);
+
+        let mut m = Move::new();
+        m.add_half_move(half_move(0, 3));
+        m.add_half_move(half_move(5, 7));
+        assert_eq!(m.to_notation(&board), "24/21* 19/17*");
+        assert_eq!(m.to_string(), "24/21 19/17");
+    }
+
+    #[test]
+    fn test_display_double_same_point() {
+        let mut m = Move::new();
+        for _ in 0..4 {
+            m.add_half_move(half_move(6, 8));
+        }
+        assert_eq!(m.to_string(), "18/16(4)");
+
+        let mut board = Board::empty();
+        board.set_active_player_checker_on_position(6, 4);
+        board.set_passive_player_checker_on_position(8, 1);
+        assert_eq!(m.to_notation(&board), "18/16*(4)");
+    }
+
+    #[test]
+    fn test_from_str_round_trip() {
+        for notation in ["24/18 13/11", "18/16(4)", "bar/20 6/off", "8/5(2) 6/5(2)", ""] {
+            let m = notation.parse::<Move>().unwrap();
+            assert_eq!(m.to_string(), notation);
+        }
+    }
+
+    #[test]
+    fn test_from_str_ignores_hits_and_expands_chains() {
+        let m = "24/21* 19/17*".parse::<Move>().unwrap();
+        assert_eq!(m.to_string(), "24/21 19/17");
+        let m = "24/18/13".parse::<Move>().unwrap();
+        assert_eq!(m.len(), 2);
+        assert_eq!(m.to_string(), "24/18 18/13");
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        assert_eq!("6/4(5)".parse::<Move>().unwrap_err(), ParseMoveError::TooManyHalfMoves);
+        assert_eq!("6/4(2) 8/4(2) 13/11".parse::<Move>().unwrap_err(), ParseMoveError::TooManyHalfMoves);
+        assert_eq!("25/20".parse::<Move>().unwrap_err(), ParseMoveError::InvalidPoint("25".to_string()));
+        assert_eq!("off/20".parse::<Move>().unwrap_err(), ParseMoveError::InvalidPoint("off".to_string()));
+        assert_eq!("6/bar".parse::<Move>().unwrap_err(), ParseMoveError::InvalidPoint("bar".to_string()));
+        assert_eq!("6".parse::<Move>().unwrap_err(), ParseMoveError::Malformed("6".to_string()));
+        assert_eq!("6/4(x)".parse::<Move>().unwrap_err(), ParseMoveError::Malformed("6/4(x)".to_string()));
+    }
+}
@@ -1,7 +1,379 @@
-use crate::backgammon::{Board, Player};
+use core::fmt::Display;
 
+use rand::Rng;
+
+use crate::backgammon::{Board, Dice, Move, MoveError, Player};
+
+/// Error returned by [`Game::load`] for text that is not a saved game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The text does not start with the header written by [`Game::save`].
+    MissingHeader,
+    /// A line is not a known key followed by a valid value. `line` counts from 1.
+    Malformed { line: usize, content: String },
+    /// A recorded move cannot be played when replaying the game.
+    IllegalMove { line: usize, error: MoveError },
+    /// The saved position or player to move differs from the one reached by replaying the moves.
+    PositionMismatch,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::MissingHeader => write!(f, "missing header \"{}\"", Game::SAVE_HEADER),
+            LoadError::Malformed { line, content } => write!(f, "malformed line {}: {}", line, content),
+            LoadError::IllegalMove { line, error } => write!(f, "illegal move on line {}: {}", line, error),
+            LoadError::PositionMismatch => write!(f, "saved position does not match the played moves"),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+/// A game in progress. Keeps the current roll and the played moves, so moves can be taken back.
+#[derive(Debug, Clone)]
 pub struct Game {
     board: Board,
-    active_player: Player,
-    home: [u8; 2],
-}
\ No newline at end of file
+    dice: Option<Dice>,
+    /// Board and roll before each played move.
+    history: Vec<(Board, Dice)>,
+    /// Roll and move of every played turn, starting with the first move.
+    moves: Vec<(Dice, Move)>,
+    /// Length of the match this game belongs to, 0 for a money game.
+    match_length: u8,
+    /// Match score before this game, indexed by White and Black.
+    score: [u8; 2],
+}
+
+impl Game {
+    const SAVE_HEADER: &'static str = "backgammon-game 1";
+
+    /// Creates a game in the starting position with no dice rolled.
+    pub fn new() -> Self {
+        Game {
+            board: Board::new(),
+            dice: None,
+            history: Vec::new(),
+            moves: Vec::new(),
+            match_length: 0,
+            score: [0, 0],
+        }
+    }
+
+    /// Creates the first game of a match to `match_length` points.
+    pub fn new_match(match_length: u8) -> Self {
+        Game { match_length, ..Self::new() }
+    }
+
+    pub fn match_length(&self) -> u8 {
+        self.match_length
+    }
+
+    /// Match score of `player` before this game.
+    pub fn score(&self, player: Player) -> u8 {
+        self.score[player as usize]
+    }
+
+    pub fn set_score(&mut self, white: u8, black: u8) {
+        self.score = [white, black];
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn active_player(&self) -> Player {
+        self.board.active_player()
+    }
+
+    /// The roll the active player has to play, if the dice have been rolled.
+    pub fn dice(&self) -> Option<Dice> {
+        self.dice
+    }
+
+    /// Roll and move of every played turn.
+    pub fn moves(&self) -> &[(Dice, Move)] {
+        &self.moves
+    }
+
+    /// Rolls the dice for the active player.
+    pub fn roll(&mut self) -> Dice {
+        self.roll_with(&mut rand::rng())
+    }
+
+    /// Rolls the dice for the active player with the given random number generator.
+    pub fn roll_with<R: Rng>(&mut self, rng: &mut R) -> Dice {
+        let dice = Dice::roll_with(rng);
+        self.dice = Some(dice);
+        dice
+    }
+
+    /// Sets the roll of the active player, e.g. to replay a recorded game.
+    pub fn set_dice(&mut self, dice: Dice) {
+        self.dice = Some(dice);
+    }
+
+    /// Plays `m` with the current roll and passes the turn to the opponent.
+    pub fn apply_move(&mut self, m: Move) -> Result<(), MoveError> {
+        let dice = self.dice.ok_or(MoveError::DiceNotRolled)?;
+        let board = self.board;
+        self.board.make_move(m, dice)?;
+        self.history.push((board, dice));
+        self.moves.push((dice, m));
+        self.dice = None;
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Takes back the last move, restoring the board and the roll it was played with.
+    /// Returns false if no move has been played.
+    pub fn undo(&mut self) -> bool {
+        let Some((board, dice)) = self.history.pop() else {
+            return false;
+        };
+        self.board = board;
+        self.dice = Some(dice);
+        self.moves.pop();
+        true
+    }
+
+    /// Exports the played moves as a GNU Backgammon `.mat` match transcript,
+    /// with one numbered line per pair of turns.
+    pub fn to_mat(&self) -> String {
+        let mut mat = format!(" {} point match\n\n Game 1\n", self.match_length);
+        mat.push_str(&format!(" White : {:<27}Black : {}\n", self.score[0], self.score[1]));
+
+        let turn = |(dice, m): &(Dice, Move)| {
+            let (high, low) = (dice.die1().max(dice.die2()), dice.die1().min(dice.die2()));
+            format!("{}{}: {}", high, low, m)
+        };
+        for (number, turns) in self.moves.chunks(2).enumerate() {
+            let mut line = format!("{:>3}) {:<31}", number + 1, turn(&turns[0]));
+            if let Some(second) = turns.get(1) {
+                line.push_str(&turn(second));
+            }
+            mat.push_str(line.trim_end());
+            mat.push('\n');
+        }
+        mat
+    }
+
+    /// Serializes the game into a line based text format that [`Self::load`] reads back:
+    ///
+    /// ```text
+    /// backgammon-game 1
+    /// match 5
+    /// score 2 1
+    /// move 3/1: 8/5 6/5
+    /// turn black
+    /// position 2 0 0 0 0 -5 0 -3 0 0 0 5 -5 0 0 0 3 0 5 0 0 0 0 -2 bar 0 0 off 0 0
+    /// dice 6/5
+    /// ```
+    ///
+    /// Moves keep the order of the dice as rolled. The position is given from White's perspective
+    /// like in [`Board::to_fancy_string`], followed by the bars and borne off checkers of White and Black.
+    /// `dice -` means the active player has not rolled yet.
+    pub fn save(&self) -> String {
+        let mut lines = vec![
+            Self::SAVE_HEADER.to_string(),
+            format!("match {}", self.match_length),
+            format!("score {} {}", self.score[0], self.score[1]),
+        ];
+        for (dice, m) in &self.moves {
+            lines.push(format!("move {}: {}", dice, m).trim_end().to_string());
+        }
+        lines.push(turn_line(&self.board));
+        lines.push(position_line(&self.board));
+        match self.dice {
+            Some(dice) => lines.push(format!("dice {}", dice)),
+            None => lines.push("dice -".to_string()),
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    /// Restores a game written by [`Self::save`]. The moves are replayed from the starting position,
+    /// so the history can be undone, and the result is checked against the saved position.
+    pub fn load(s: &str) -> Result<Game, LoadError> {
+        let mut lines = s.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        if lines.next().map(|(_, line)| line) != Some(Self::SAVE_HEADER) {
+            return Err(LoadError::MissingHeader);
+        }
+
+        let mut game = Game::new();
+        let (mut turn, mut position, mut dice) = (None, None, None);
+        for (number, line) in lines {
+            let malformed = || LoadError::Malformed { line: number, content: line.to_string() };
+            let (key, value) = line.split_once(' ').map_or((line, ""), |(key, value)| (key, value.trim()));
+            match key {
+                "match" => game.match_length = value.parse().map_err(|_| malformed())?,
+                "score" => {
+                    let (white, black) = value.split_once(' ').ok_or_else(malformed)?;
+                    game.score = [white.parse().map_err(|_| malformed())?, black.trim().parse().map_err(|_| malformed())?];
+                }
+                "move" => {
+                    let (roll, notation) = value.split_once(':').ok_or_else(malformed)?;
+                    game.set_dice(roll.trim().parse().map_err(|_| malformed())?);
+                    let m = notation.parse().map_err(|_| malformed())?;
+                    game.apply_move(m).map_err(|error| LoadError::IllegalMove { line: number, error })?;
+                }
+                "turn" => turn = Some(line),
+                "position" => position = Some(line),
+                "dice" if value == "-" => dice = None,
+                "dice" => dice = Some(value.parse().map_err(|_| malformed())?),
+                _ => return Err(malformed()),
+            }
+        }
+
+        if turn != Some(turn_line(&game.board).as_str()) || position != Some(position_line(&game.board).as_str()) {
+            return Err(LoadError::PositionMismatch);
+        }
+        game.dice = dice;
+        Ok(game)
+    }
+}
+
+fn turn_line(board: &Board) -> String {
+    match board.active_player() {
+        Player::White => "turn white".to_string(),
+        Player::Black => "turn black".to_string(),
+    }
+}
+
+fn position_line(board: &Board) -> String {
+    let board = board.from_whites_perspective();
+    let points = (0..24).map(|index| board.get_checkers_on_position(index).to_string()).collect::<Vec<_>>();
+    format!("position {} bar {} {} off {} {}", points.join(" "), board.get_active_bar(), board.get_passive_bar(), board.get_active_home(), board.get_passive_home())
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backgammon::GameOutcome;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_then_undo() {
+        let mut game = Game::new();
+        assert!(!game.can_undo());
+        assert!(!game.undo());
+
+        let dice = Dice::from_numbers(3, 1);
+        assert_eq!(game.apply_move("8/5 6/5".parse().unwrap()), Err(MoveError::DiceNotRolled));
+        game.set_dice(dice);
+        let before = *game.board();
+        game.apply_move("8/5 6/5".parse().unwrap()).unwrap();
+        assert_eq!(game.active_player(), Player::Black);
+        assert_eq!(game.moves().len(), 1);
+        assert_eq!(game.dice(), None);
+
+        assert!(game.undo());
+        assert_eq!(*game.board(), before);
+        assert_eq!(game.active_player(), Player::White);
+        assert_eq!(game.dice(), Some(dice));
+        assert!(game.moves().is_empty());
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn test_to_mat() {
+        let mut game = Game::new_match(5);
+        game.set_score(2, 1);
+        for (dice, m) in [((1, 3), "8/5 6/5"), ((4, 2), "8/4 6/4"), ((6, 5), "24/18 13/8")] {
+            game.set_dice(Dice::from_numbers(dice.0, dice.1));
+            game.apply_move(m.parse().unwrap()).unwrap();
+        }
+        let expected = [
+            " 5 point match",
+            "",
+            " Game 1",
+            " White : 2                          Black : 1",
+            "  1) 31: 8/5 6/5                    42: 8/4 6/4",
+            "  2) 65: 24/18 13/8",
+            "",
+        ].join("\n");
+        assert_eq!(game.to_mat(), expected);
+    }
+
+    #[test]
+    fn test_illegal_move_keeps_history() {
+        let mut game = Game::new();
+        game.set_dice(Dice::from_numbers(3, 1));
+        assert!(game.apply_move("8/4".parse().unwrap()).is_err());
+        assert!(!game.can_undo());
+        assert_eq!(game.dice(), Some(Dice::from_numbers(3, 1)));
+    }
+
+    #[test]
+    fn test_save_load() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        fn assert_round_trip(game: &Game) {
+            let saved = game.save();
+            let loaded = Game::load(&saved).unwrap();
+            assert_eq!(loaded.save(), saved);
+            assert_eq!(loaded.board(), game.board());
+            assert_eq!(loaded.moves(), game.moves());
+            assert_eq!(loaded.dice(), game.dice());
+            assert_eq!((loaded.match_length(), loaded.score(Player::White), loaded.score(Player::Black)), (7, 3, 5));
+
+            if let Some(dice) = game.dice() && game.board().outcome() == GameOutcome::Ongoing {
+                let moves = game.board().generate_moves(dice);
+                assert_eq!(loaded.board().generate_moves(dice), moves);
+                let (mut game, mut loaded) = (game.clone(), loaded.clone());
+                game.apply_move(moves[0]).unwrap();
+                loaded.apply_move(moves[0]).unwrap();
+                assert_eq!(loaded.save(), game.save());
+            }
+        }
+
+        // Full games with moves from `generate_moves`, whose half moves are not always in an
+        // order the saved notation keeps, e.g. `bar/23* 22/20(2) 24/22` with 2-2.
+        let mut rng = StdRng::seed_from_u64(9);
+        for _ in 0..20 {
+            let mut game = Game::new_match(7);
+            game.set_score(3, 5);
+            while game.board().outcome() == GameOutcome::Ongoing {
+                let dice = game.roll_with(&mut rng);
+                if game.moves().len() == 40 {
+                    assert_round_trip(&game);
+                }
+                let m = *game.board().generate_moves(dice).choose(&mut rng).unwrap();
+                game.apply_move(m).unwrap();
+            }
+            assert_round_trip(&game);
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(Game::load("match 5").unwrap_err(), LoadError::MissingHeader);
+
+        let mut game = Game::new();
+        game.set_dice(Dice::from_numbers(3, 1));
+        game.apply_move("8/5 6/5".parse().unwrap()).unwrap();
+        let saved = game.save();
+        assert!(Game::load(&saved.replace("dice -", "dice 7/1")).is_err());
+        assert_eq!(
+            Game::load(&saved.replace("move 3/1: 8/5 6/5", "move 3/1: 8/5")).unwrap_err(),
+            LoadError::IllegalMove { line: 4, error: MoveError::NotInLegalSet },
+        );
+        assert_eq!(Game::load(&saved.replace("turn black", "turn white")).unwrap_err(), LoadError::PositionMismatch);
+        assert_eq!(
+            Game::load(&saved.replace("score 0 0", "score 0")).unwrap_err(),
+            LoadError::Malformed { line: 3, content: "score 0".to_string() },
+        );
+    }
+}
@@ -1,8 +1,9 @@
-use std::fmt::Debug;
+use core::fmt::Debug;
 
-use crate::backgammon::{Position, PositionCompressed};
+use crate::backgammon::{Dice, Position, PositionCompressed};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HalfMove {
     from: PositionCompressed,
     to: PositionCompressed,
@@ -27,10 +28,85 @@ impl HalfMove {
     pub fn to(&self) -> &PositionCompressed {
         &self.to
     }
+
+    /// Returns true if the half move bears a checker off.
+    pub fn is_bear_off(&self) -> bool {
+        self.to == PositionCompressed::HOME
+    }
+
+    /// Returns true if the half move enters a checker from the bar.
+    pub fn is_bar_entry(&self) -> bool {
+        self.from == PositionCompressed::BAR
+    }
+
+    /// Number of pips the checker travels: `to + 1` when entering from the bar and the exact
+    /// distance to home when bearing off, which a larger die may overshoot, see [`Self::used_die`].
+    /// `None` if the half move does not move forward by 1 to 6 pips.
+    pub fn die_value(&self) -> Option<u8> {
+        let pips = match (Position::from(self.from), Position::from(self.to)) {
+            (Position::Bar, Position::Board(to)) => to + 1,
+            (Position::Board(from), Position::Board(to)) if to > from => to - from,
+            (Position::Board(from), Position::Home) => 24 - from,
+            _ => return None,
+        };
+        (1..=6).contains(&pips).then_some(pips)
+    }
+
+    /// The die of `dice` this half move consumes: the die matching [`Self::die_value`], or for a
+    /// bear-off without a matching die the smallest larger one. `None` if no available die fits.
+    pub fn used_die(&self, dice: Dice) -> Option<u8> {
+        let pips = self.die_value()?;
+        let available = dice.availiable_dice();
+        if available.contains(&pips) {
+            Some(pips)
+        } else if self.is_bear_off() {
+            available.iter().copied().filter(|&die| die > pips).min()
+        } else {
+            None
+        }
+    }
 }
 
 impl Debug for HalfMove {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:?} -> {:?}", self.from, self.to)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_die_value_normal_hop() {
+        let half_move = HalfMove::from_position(Position::Board(7), Position::Board(12));
+        assert_eq!(half_move.die_value(), Some(5));
+        assert_eq!(half_move.used_die(Dice::from_numbers(2, 5)), Some(5));
+        assert_eq!(half_move.used_die(Dice::from_numbers(2, 6)), None);
+        assert!(!half_move.is_bear_off());
+        assert!(!half_move.is_bar_entry());
+        assert_eq!(HalfMove::from_position(Position::Board(12), Position::Board(7)).die_value(), None);
+        assert_eq!(HalfMove::from_position(Position::Board(0), Position::Board(7)).die_value(), None);
+    }
+
+    #[test]
+    fn test_die_value_bar_entry() {
+        let half_move = HalfMove::from_position(Position::Bar, Position::Board(3));
+        assert!(half_move.is_bar_entry());
+        assert_eq!(half_move.die_value(), Some(4));
+        assert_eq!(half_move.used_die(Dice::from_numbers(4, 1)), Some(4));
+    }
+
+    #[test]
+    fn test_die_value_bear_off_overshoot() {
+        let half_move = HalfMove::from_position(Position::Board(22), Position::Home);
+        assert!(half_move.is_bear_off());
+        assert_eq!(half_move.die_value(), Some(2));
+        assert_eq!(half_move.used_die(Dice::from_numbers(5, 6)), Some(5));
+        assert_eq!(half_move.used_die(Dice::from_numbers(2, 6)), Some(2));
+        assert_eq!(half_move.used_die(Dice::from_numbers(1, 1)), None);
+        let mut dice = Dice::from_numbers(6, 5);
+        dice.use_die2();
+        assert_eq!(half_move.used_die(dice), Some(6));
+    }
+}
@@ -0,0 +1,336 @@
+use core::fmt::Display;
+
+use crate::backgammon::{Dice, GameOutcome, Player};
+
+/// Error returned by [`Match::from_match_id`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchIdError {
+    /// The ID is not 12 characters of standard base64.
+    Malformed,
+    /// A field holds a value that cannot be represented, e.g. a money game, a die of 7 or a score above 255.
+    InvalidField(&'static str),
+}
+
+impl Display for MatchIdError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MatchIdError::Malformed => write!(f, "match ID is not 12 base64 characters"),
+            MatchIdError::InvalidField(field) => write!(f, "match ID has an invalid {}", field),
+        }
+    }
+}
+
+impl core::error::Error for MatchIdError {}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Score of a match to a fixed number of points, including the Crawford rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    length: u8,
+    /// Points won so far, indexed by White and Black.
+    score: [u8; 2],
+    /// Whether the Crawford game has already been played.
+    crawford_played: bool,
+    /// Value of the doubling cube in the current game.
+    cube: u8,
+    /// Player who may redouble, `None` while the cube is centered.
+    cube_owner: Option<Player>,
+    /// Player on roll in the current game.
+    turn: Player,
+    /// Roll of the player on roll, `None` before the dice are rolled.
+    dice: Option<Dice>,
+}
+
+impl Match {
+    /// Creates a match to `length` points with no games played. `length` must be at least 1.
+    pub fn new(length: u8) -> Self {
+        Match {
+            length,
+            score: [0, 0],
+            crawford_played: false,
+            cube: 1,
+            cube_owner: None,
+            turn: Player::White,
+            dice: None,
+        }
+    }
+
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    pub fn score(&self, player: Player) -> u8 {
+        self.score[player as usize]
+    }
+
+    pub fn cube(&self) -> u8 {
+        self.cube
+    }
+
+    /// Sets the cube value of the current game, e.g. after an accepted double.
+    pub fn set_cube(&mut self, cube: u8) {
+        self.cube = cube;
+    }
+
+    pub fn cube_owner(&self) -> Option<Player> {
+        self.cube_owner
+    }
+
+    /// Gives the cube to `owner`, e.g. the player who accepted a double. `None` centers it.
+    pub fn set_cube_owner(&mut self, owner: Option<Player>) {
+        self.cube_owner = owner;
+    }
+
+    /// Player on roll in the current game.
+    pub fn turn(&self) -> Player {
+        self.turn
+    }
+
+    pub fn set_turn(&mut self, player: Player) {
+        self.turn = player;
+    }
+
+    /// Roll of the player on roll, if the dice have been rolled.
+    pub fn dice(&self) -> Option<Dice> {
+        self.dice
+    }
+
+    pub fn set_dice(&mut self, dice: Option<Dice>) {
+        self.dice = dice;
+    }
+
+    pub fn crawford_played(&self) -> bool {
+        self.crawford_played
+    }
+
+    /// The first game after a player reaches `length - 1` points is the Crawford game.
+    pub fn is_crawford_game(&self) -> bool {
+        !self.crawford_played && !self.is_over() && self.score.contains(&(self.length - 1))
+    }
+
+    /// Doubling is not allowed during the Crawford game or after the match has ended.
+    pub fn doubling_allowed(&self) -> bool {
+        !self.is_over() && !self.is_crawford_game()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    /// Player who reached the match length, if any.
+    pub fn winner(&self) -> Option<Player> {
+        [Player::White, Player::Black].into_iter().find(|&player| self.score(player) >= self.length)
+    }
+
+    /// Adds the points of a finished game played with the cube at `cube` and starts the next game.
+    /// Ongoing outcomes are ignored. Scores saturate at `u8::MAX`, which ends any match.
+    pub fn record_game(&mut self, outcome: GameOutcome, cube: u8) {
+        let Some(winner) = outcome.winner() else {
+            return;
+        };
+        if self.is_crawford_game() {
+            self.crawford_played = true;
+        }
+        self.score[winner as usize] = self.score[winner as usize].saturating_add(outcome.points_with_cube(cube));
+        self.cube = 1;
+        self.cube_owner = None;
+        self.dice = None;
+    }
+
+    /// Encodes the match as a GNU Backgammon Match ID, 12 base64 characters holding 66 bits:
+    ///
+    /// | bits  | field                                              |
+    /// |-------|----------------------------------------------------|
+    /// | 0-3   | log2 of the cube value                             |
+    /// | 4-5   | cube owner: 0 White, 1 Black, 3 centered           |
+    /// | 6     | player on roll                                     |
+    /// | 7     | Crawford game                                      |
+    /// | 8-10  | game state: 1 playing, 2 over                      |
+    /// | 11    | player to make a decision, the player on roll here |
+    /// | 12    | double offered, always 0                           |
+    /// | 13-14 | resignation offered, always 0                      |
+    /// | 15-20 | dice, 0 if not rolled                              |
+    /// | 21-35 | match length                                       |
+    /// | 36-65 | score of White and Black                           |
+    ///
+    /// White is GNU Backgammon's player 0 and Black player 1. Fields are written least significant bit
+    /// first into 9 bytes, which are then base64 encoded.
+    #[cfg(feature = "std")]
+    pub fn to_match_id(&self) -> String {
+        let player = |player: Player| player as u128;
+        let cube_owner = self.cube_owner.map_or(3, player);
+        let state = if self.is_over() { 2 } else { 1 };
+        let (die1, die2) = self.dice.map_or((0, 0), |dice| (dice.die1(), dice.die2()));
+        let bits = self.cube.trailing_zeros() as u128
+            | cube_owner << 4
+            | player(self.turn) << 6
+            | (self.is_crawford_game() as u128) << 7
+            | state << 8
+            | player(self.turn) << 11
+            | (die1 as u128) << 15
+            | (die2 as u128) << 18
+            | (self.length as u128) << 21
+            | (self.score[0] as u128) << 36
+            | (self.score[1] as u128) << 51;
+
+        let bytes = &bits.to_le_bytes()[..9];
+        let mut id = String::with_capacity(12);
+        for chunk in bytes.chunks(3) {
+            let group = (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32;
+            for shift in [18, 12, 6, 0] {
+                id.push(BASE64[(group >> shift & 0x3F) as usize] as char);
+            }
+        }
+        id
+    }
+
+    /// Decodes a GNU Backgammon Match ID, see [`Self::to_match_id`] for the layout.
+    /// Offered doubles and resignations are not part of [`Match`] and are ignored,
+    /// the Crawford bit decides whether the Crawford game has already been played.
+    pub fn from_match_id(id: &str) -> Result<Match, MatchIdError> {
+        if id.len() != 12 {
+            return Err(MatchIdError::Malformed);
+        }
+        let mut bytes = [0u8; 16];
+        for (i, chunk) in id.as_bytes().chunks(4).enumerate() {
+            let mut group = 0u32;
+            for &c in chunk {
+                let value = BASE64.iter().position(|&b| b == c).ok_or(MatchIdError::Malformed)?;
+                group = group << 6 | value as u32;
+            }
+            bytes[3 * i..3 * i + 3].copy_from_slice(&group.to_be_bytes()[1..]);
+        }
+        let bits = u128::from_le_bytes(bytes);
+        let field = |offset: u32, width: u32| (bits >> offset & ((1 << width) - 1)) as u32;
+        let player = |bit: u32| if bit == 0 { Player::White } else { Player::Black };
+
+        let cube_log = field(0, 4);
+        if cube_log > 6 {
+            return Err(MatchIdError::InvalidField("cube value"));
+        }
+        let cube_owner = match field(4, 2) {
+            3 => None,
+            2 => return Err(MatchIdError::InvalidField("cube owner")),
+            owner => Some(player(owner)),
+        };
+        let dice = match (field(15, 3), field(18, 3)) {
+            (0, 0) => None,
+            (die1 @ 1..=6, die2 @ 1..=6) => Some(Dice::from_numbers(die1 as u8, die2 as u8)),
+            _ => return Err(MatchIdError::InvalidField("dice")),
+        };
+        let length = u8::try_from(field(21, 15)).ok().filter(|&length| length > 0)
+            .ok_or(MatchIdError::InvalidField("match length"))?;
+        let score = |offset| u8::try_from(field(offset, 15)).map_err(|_| MatchIdError::InvalidField("score"));
+
+        let mut m = Match {
+            cube: 1 << cube_log,
+            cube_owner,
+            turn: player(field(6, 1)),
+            dice,
+            score: [score(36)?, score(51)?],
+            ..Match::new(length)
+        };
+        let crawford = field(7, 1) == 1;
+        if crawford && !m.is_crawford_game() {
+            return Err(MatchIdError::InvalidField("Crawford flag"));
+        }
+        m.crawford_played = !crawford && !m.is_over() && m.score.contains(&(length - 1));
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_to_completion() {
+        let mut m = Match::new(5);
+        assert!(m.doubling_allowed());
+
+        m.record_game(GameOutcome::Gammon(Player::White), 1);
+        m.record_game(GameOutcome::Win(Player::Black), 2);
+        assert_eq!((m.score(Player::White), m.score(Player::Black)), (2, 2));
+        assert!(!m.is_crawford_game());
+
+        m.record_game(GameOutcome::Win(Player::White), 2);
+        assert_eq!(m.score(Player::White), 4);
+        assert!(m.is_crawford_game());
+        assert!(!m.doubling_allowed());
+
+        m.record_game(GameOutcome::Win(Player::Black), 1);
+        assert!(m.crawford_played());
+        assert!(!m.is_crawford_game());
+        assert!(m.doubling_allowed());
+
+        m.record_game(GameOutcome::Win(Player::Black), 2);
+        assert_eq!((m.score(Player::White), m.score(Player::Black)), (4, 5));
+        assert_eq!(m.winner(), Some(Player::Black));
+        assert!(!m.doubling_allowed());
+    }
+
+    #[test]
+    fn test_record_game_saturates() {
+        let mut m = Match::new(255);
+        m.record_game(GameOutcome::Backgammon(Player::White), 64);
+        assert_eq!(m.score(Player::White), 192);
+        m.record_game(GameOutcome::Backgammon(Player::White), 128);
+        assert_eq!(m.score(Player::White), 255);
+        assert_eq!(m.winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn test_ongoing_is_ignored() {
+        let mut m = Match::new(3);
+        m.set_cube(2);
+        m.record_game(GameOutcome::Ongoing, 2);
+        assert_eq!(m, Match { cube: 2, ..Match::new(3) });
+    }
+
+    #[test]
+    fn test_match_id_documentation_example() {
+        // Example from the GNU Backgammon manual: 9 point match at 2-4, cube at 2 owned by player 0,
+        // player 1 on roll with 52 rolled.
+        let m = Match::from_match_id("QYkqASAAIAAA").unwrap();
+        assert_eq!((m.length(), m.score(Player::White), m.score(Player::Black)), (9, 2, 4));
+        assert_eq!((m.cube(), m.cube_owner()), (2, Some(Player::White)));
+        assert_eq!(m.turn(), Player::Black);
+        assert_eq!(m.dice(), Some(Dice::from_numbers(5, 2)));
+        assert!(!m.is_crawford_game());
+        assert_eq!(m.to_match_id(), "QYkqASAAIAAA");
+    }
+
+    #[test]
+    fn test_match_id_dice_not_rolled() {
+        let mut m = Match::new(7);
+        m.set_turn(Player::Black);
+        assert_eq!(m.to_match_id(), "cAngAAAAAAAA");
+        assert_eq!(Match::from_match_id("cAngAAAAAAAA"), Ok(m));
+        // Money games, as in the initial "cAkAAAAAAAAA" of GNU Backgammon, have no match length.
+        assert_eq!(Match::from_match_id("cAkAAAAAAAAA"), Err(MatchIdError::InvalidField("match length")));
+        assert_eq!(Match::from_match_id("cAngAAAAAAA"), Err(MatchIdError::Malformed));
+        assert_eq!(Match::from_match_id("cAngAAAAAAA!"), Err(MatchIdError::Malformed));
+    }
+
+    #[test]
+    fn test_match_id_round_trip() {
+        let mut m = Match::new(5);
+        m.record_game(GameOutcome::Gammon(Player::White), 2);
+        for (cube_owner, dice) in [(None, None), (Some(Player::Black), Some(Dice::from_numbers(6, 6))), (Some(Player::White), Some(Dice::from_numbers(1, 3)))] {
+            m.set_cube(4);
+            m.set_cube_owner(cube_owner);
+            m.set_dice(dice);
+            assert_eq!(Match::from_match_id(&m.to_match_id()), Ok(m));
+        }
+
+        // The Crawford game and the games after it.
+        let mut m = Match::new(5);
+        m.record_game(GameOutcome::Gammon(Player::White), 2);
+        assert!(m.is_crawford_game());
+        assert_eq!(Match::from_match_id(&m.to_match_id()), Ok(m));
+        m.record_game(GameOutcome::Win(Player::Black), 1);
+        assert!(m.crawford_played());
+        assert_eq!(Match::from_match_id(&m.to_match_id()), Ok(m));
+    }
+}
@@ -1,5 +1,8 @@
 mod board;
 pub use board::Board;
+pub use board::{MoveError, PositionError};
+#[cfg(feature = "std")]
+pub use board::ParseDiagramError;
 
 mod player;
 pub use player::Player;
@@ -7,18 +10,45 @@ pub use player::Player;
 mod position;
 pub use position::Position;
 pub use position::PositionCompressed;
+pub use position::InvalidPointIndex;
 
 mod outcome;
 pub use outcome::GameOutcome;
 
+mod phase;
+pub use phase::GamePhase;
+
+#[cfg(feature = "std")]
 mod game;
-pub use game::Game;
+#[cfg(feature = "std")]
+pub use game::{Game, LoadError};
+
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+pub use replay::Replay;
+
+mod match_state;
+pub use match_state::{Match, MatchIdError};
 
 mod halfmove;
 pub use halfmove::HalfMove;
 
 mod full_move;
 pub use full_move::Move;
+#[cfg(feature = "std")]
+pub use full_move::ParseMoveError;
+pub use full_move::TooManyHalfMoves;
 
 mod dice;
-pub use dice::Dice;
\ No newline at end of file
+pub use dice::Dice;
+#[cfg(feature = "std")]
+pub use dice::ParseDiceError;
+
+pub(crate) mod zobrist;
+
+#[cfg(feature = "std")]
+pub mod bearoff;
+
+#[cfg(feature = "std")]
+pub mod opening_book;
@@ -0,0 +1,62 @@
+//! Best plays for the first move of a game.
+//!
+//! The player moving first plays one of the 15 non-double rolls from the starting position,
+//! for which the best plays are well established. Where several plays are about equal the
+//! book holds the most common one.
+
+use crate::backgammon::{Dice, Move};
+
+/// Opening plays by their [`canonical`](Dice::canonical) roll.
+const BOOK: [((u8, u8), &str); 15] = [
+    ((1, 2), "13/11 6/5"),
+    ((1, 3), "8/5 6/5"),
+    ((1, 4), "24/23 13/9"),
+    ((1, 5), "24/23 13/8"),
+    ((1, 6), "13/7 8/7"),
+    ((2, 3), "24/21 13/11"),
+    ((2, 4), "8/4 6/4"),
+    ((2, 5), "13/11 13/8"),
+    ((2, 6), "24/18 13/11"),
+    ((3, 4), "24/20 13/10"),
+    ((3, 5), "8/3 6/3"),
+    ((3, 6), "24/18 13/10"),
+    ((4, 5), "24/20 13/8"),
+    ((4, 6), "8/2 6/2"),
+    ((5, 6), "24/18 18/13"),
+];
+
+/// Best play for `dice` as the first move of the game. Returns `None` for doubles,
+/// which can not be rolled on the first move.
+pub fn lookup(dice: Dice) -> Option<Move> {
+    BOOK.iter()
+        .find(|(roll, _)| *roll == dice.canonical())
+        .map(|(_, play)| play.parse().expect("Opening book plays are valid moves"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backgammon::Board;
+
+    #[test]
+    fn test_book_plays_are_legal() {
+        for dice in Dice::ALL {
+            let Some(play) = lookup(dice) else {
+                assert!(dice.is_double());
+                continue;
+            };
+            assert!(!dice.is_double());
+            assert!(Board::new().generate_moves(dice).iter().any(|m| m.unordered_equal(&play)), "{dice} {play}");
+        }
+    }
+
+    #[test]
+    fn test_textbook_plays() {
+        let play = |die1, die2| lookup(Dice::from_numbers(die1, die2)).unwrap();
+        assert!(play(3, 1).unordered_equal(&"8/5 6/5".parse().unwrap()));
+        assert!(play(1, 3).unordered_equal(&play(3, 1)));
+        assert!(play(4, 2).unordered_equal(&"8/4 6/4".parse().unwrap()));
+        assert!(play(6, 1).unordered_equal(&"13/7 8/7".parse().unwrap()));
+        assert!(lookup(Dice::from_numbers(5, 5)).is_none());
+    }
+}
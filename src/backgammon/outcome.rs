@@ -1,9 +1,60 @@
+use crate::backgammon::Player;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameOutcome {
+    Win(Player),
+    Gammon(Player),
+    Backgammon(Player),
     Ongoing,
-    ActivePlayerWin,
-    PassivePlayerWin,
-    ActivePlayerGammon,
-    PassivePlayerGammon,
-    ActivePlayerBackgammon,
-    PassivePlayerBackgammon,
-}
\ No newline at end of file
+}
+
+impl GameOutcome {
+    /// Player who won the game, `None` while it is ongoing.
+    pub fn winner(&self) -> Option<Player> {
+        match *self {
+            GameOutcome::Win(player) | GameOutcome::Gammon(player) | GameOutcome::Backgammon(player) => Some(player),
+            GameOutcome::Ongoing => None,
+        }
+    }
+
+    /// Points won without the doubling cube: 1 for a win, 2 for a gammon and 3 for a backgammon.
+    pub fn base_points(&self) -> u8 {
+        match self {
+            GameOutcome::Win(_) => 1,
+            GameOutcome::Gammon(_) => 2,
+            GameOutcome::Backgammon(_) => 3,
+            GameOutcome::Ongoing => 0,
+        }
+    }
+
+    /// Points won with the doubling cube at `cube`, saturating at `u8::MAX`.
+    pub fn points_with_cube(&self, cube: u8) -> u8 {
+        self.base_points().saturating_mul(cube)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let outcomes = [
+            GameOutcome::Win(Player::White),
+            GameOutcome::Gammon(Player::Black),
+            GameOutcome::Backgammon(Player::White),
+        ];
+        for (outcome, points) in outcomes.iter().zip(1..) {
+            assert_eq!(outcome.base_points(), points);
+            assert_eq!(outcome.points_with_cube(4), 4 * points);
+        }
+        assert_eq!(outcomes.map(|o| o.winner()), [Some(Player::White), Some(Player::Black), Some(Player::White)]);
+    }
+
+    #[test]
+    fn test_ongoing() {
+        assert_eq!(GameOutcome::Ongoing.winner(), None);
+        assert_eq!(GameOutcome::Ongoing.base_points(), 0);
+        assert_eq!(GameOutcome::Ongoing.points_with_cube(2), 0);
+    }
+}
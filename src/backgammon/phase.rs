@@ -0,0 +1,15 @@
+/// Phase of the game, see [`crate::backgammon::Board::phase`].
+///
+/// The phases are exclusive and checked in order: a closeout takes precedence over any
+/// other contact position, and a bearoff is the final stage of a race.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamePhase {
+    /// The checkers of both players can still hit each other.
+    Contact,
+    /// No more contact is possible, but some checkers still have to come home.
+    Race,
+    /// No more contact is possible and both players have all their checkers home.
+    Bearoff,
+    /// A player has a checker on the bar against a closed home board.
+    Closeout,
+}
@@ -1,13 +1,28 @@
-use std::{fmt::Debug, num::NonZeroU8};
+use core::{fmt::{Debug, Display}, num::NonZeroU8};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Position {
     Bar,
     Home,
     Board(u8),
 }
 
+/// Error returned when converting a point index outside of 0 to 23 to a [`Position`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidPointIndex(pub u8);
+
+impl Display for InvalidPointIndex {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "point index {} is out of range, expected 0 to 23", self.0)
+    }
+}
+
+impl core::error::Error for InvalidPointIndex {}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "Position", try_from = "SerializedPosition"))]
 pub struct PositionCompressed {
     data: NonZeroU8,
 }
@@ -21,26 +36,88 @@ impl PositionCompressed {
         self.data.get()
     }
 
-    /// Creates a Position on the board from an index (0-23).
-    pub fn from_index(index: u8) -> Self {
+    /// Creates a Position on the board from an index (0-23). The index is only checked in debug
+    /// builds, outside of the crate positions are created with [`Self::try_from_index`].
+    pub(crate) fn from_index(index: u8) -> Self {
+        debug_assert!(index < 24, "point index {index} is out of range");
         PositionCompressed { data: unsafe { NonZeroU8::new_unchecked(index + 3) } }
     }
 
+    /// Creates a Position on the board from an index, `None` unless the index is below 24.
+    pub fn try_from_index(index: u8) -> Option<Self> {
+        (index < 24).then(|| Self::from_index(index))
+    }
+
     pub const BAR: Self = PositionCompressed { data: unsafe { NonZeroU8::new_unchecked(1) } };
 
     pub const HOME: Self = PositionCompressed { data: unsafe { NonZeroU8::new_unchecked(2) } };
 }
 
+impl Position {
+    /// Position the active player's checker lands on after moving `die` pips.
+    /// Moving past the last point bears the checker off.
+    pub fn advance(&self, die: u8) -> Position {
+        match *self {
+            Position::Bar => Position::Board(die - 1),
+            Position::Home => Position::Home,
+            Position::Board(index) if index + die >= 24 => Position::Home,
+            Position::Board(index) => Position::Board(index + die),
+        }
+    }
+
+    /// Number of pips the active player's checker still has to travel to be borne off.
+    pub fn pips_to_home(&self) -> u8 {
+        match *self {
+            Position::Bar => 25,
+            Position::Home => 0,
+            Position::Board(index) => 24 - index,
+        }
+    }
+}
+
+/// Panics if a [`Position::Board`] index is 24 or more, [`PositionCompressed::try_from_index`]
+/// checks untrusted indices instead.
 impl From<Position> for PositionCompressed {
     fn from(pos: Position) -> Self {
         match pos {
-            Position::Bar => PositionCompressed { data: unsafe { NonZeroU8::new_unchecked(1) } },
-            Position::Home => PositionCompressed { data: unsafe { NonZeroU8::new_unchecked(2) } },
-            Position::Board(index) => PositionCompressed { data: unsafe { NonZeroU8::new_unchecked(index + 3) } },
+            Position::Bar => PositionCompressed::BAR,
+            Position::Home => PositionCompressed::HOME,
+            Position::Board(index) => match PositionCompressed::try_from_index(index) {
+                Some(position) => position,
+                None => panic!("point index {index} is out of range"),
+            },
+        }
+    }
+}
+
+/// [`Position`] as deserialized, so that out of range point indices are rejected
+/// instead of panicking in the conversion to a [`PositionCompressed`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(transparent)]
+struct SerializedPosition(Position);
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedPosition> for PositionCompressed {
+    type Error = InvalidPointIndex;
+
+    fn try_from(serialized: SerializedPosition) -> Result<Self, Self::Error> {
+        match serialized.0 {
+            Position::Board(index) => PositionCompressed::try_from_index(index).ok_or(InvalidPointIndex(index)),
+            position => Ok(position.into()),
         }
     }
 }
 
+impl TryFrom<u8> for Position {
+    type Error = InvalidPointIndex;
+
+    /// Converts a point index to [`Position::Board`], rejecting indices of 24 and more.
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        if index < 24 { Ok(Position::Board(index)) } else { Err(InvalidPointIndex(index)) }
+    }
+}
+
 impl From<PositionCompressed> for Position {
     fn from(compressed: PositionCompressed) -> Self {
         match compressed.data.get() {
@@ -53,7 +130,7 @@ impl From<PositionCompressed> for Position {
 }
 
 impl Debug for PositionCompressed {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self.data.get() {
             1 => write!(f, "Bar"),
             2 => write!(f, "Home"),
@@ -61,4 +138,53 @@ impl Debug for PositionCompressed {
             _ => write!(f, "Invalid PositionCompressed"),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_index() {
+        assert_eq!(Position::try_from(23), Ok(Position::Board(23)));
+        assert_eq!(Position::try_from(24), Err(InvalidPointIndex(24)));
+        assert_eq!(Position::try_from(u8::MAX), Err(InvalidPointIndex(u8::MAX)));
+        assert_eq!(PositionCompressed::try_from_index(23), Some(PositionCompressed::from(Position::Board(23))));
+        assert_eq!(PositionCompressed::try_from_index(24), None);
+        assert_eq!(PositionCompressed::try_from_index(253), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "point index 253 is out of range")]
+    fn test_from_position_rejects_out_of_range_index() {
+        let _ = PositionCompressed::from(Position::Board(253));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_out_of_range_index() {
+        let json = serde_json::to_string(&PositionCompressed::from(Position::Board(23))).unwrap();
+        assert_eq!(serde_json::from_str::<PositionCompressed>(&json).unwrap(), PositionCompressed::from_index(23));
+        assert!(serde_json::from_str::<PositionCompressed>(&json.replace("23", "24")).is_err());
+    }
+
+    #[test]
+    fn test_advance_from_bar() {
+        assert_eq!(Position::Bar.advance(1), Position::Board(0));
+        assert_eq!(Position::Bar.advance(6), Position::Board(5));
+        assert_eq!(Position::Bar.pips_to_home(), 25);
+    }
+
+    #[test]
+    fn test_advance_on_board() {
+        assert_eq!(Position::Board(7).advance(5), Position::Board(12));
+        assert_eq!(Position::Board(12).pips_to_home(), Position::Board(7).pips_to_home() - 5);
+    }
+
+    #[test]
+    fn test_advance_bear_off() {
+        assert_eq!(Position::Board(20).advance(4), Position::Home);
+        assert_eq!(Position::Board(22).advance(6), Position::Home);
+        assert_eq!(Position::Board(22).pips_to_home(), 2);
+        assert_eq!(Position::Home.pips_to_home(), 0);
+    }
+}
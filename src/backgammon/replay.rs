@@ -0,0 +1,110 @@
+use crate::backgammon::{Board, Dice, Move, MoveError};
+
+/// Read-only navigation over a recorded game, e.g. for a viewer. Unlike [`crate::backgammon::Game`]
+/// it does not play new moves but steps back and forth between the positions of the record.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// Roll and move of every recorded turn.
+    moves: Vec<(Dice, Move)>,
+    /// Position before each move, followed by the final position.
+    boards: Vec<Board>,
+    /// Number of moves played to reach the current position.
+    index: usize,
+}
+
+impl Replay {
+    /// Replays `moves` from `initial` and stops at the initial position.
+    /// Fails with the error of the first move that is illegal for its roll.
+    pub fn new(initial: Board, moves: Vec<(Dice, Move)>) -> Result<Self, MoveError> {
+        let mut boards = Vec::with_capacity(moves.len() + 1);
+        let mut board = initial;
+        boards.push(board);
+        for &(dice, m) in &moves {
+            board.make_move(m, dice)?;
+            boards.push(board);
+        }
+        Ok(Replay { moves, boards, index: 0 })
+    }
+
+    /// Position after [`Self::index`] moves.
+    pub fn current(&self) -> &Board {
+        &self.boards[self.index]
+    }
+
+    /// Number of moves played to reach the current position.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of recorded moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Roll and move played from the current position, `None` at the end of the game.
+    pub fn next_move(&self) -> Option<(Dice, Move)> {
+        self.moves.get(self.index).copied()
+    }
+
+    /// Plays the next move and returns the new position, or `None` at the end of the game.
+    pub fn step_forward(&mut self) -> Option<&Board> {
+        self.seek(self.index + 1)
+    }
+
+    /// Takes back the last move and returns the new position, or `None` at the start of the game.
+    pub fn step_back(&mut self) -> Option<&Board> {
+        self.seek(self.index.checked_sub(1)?)
+    }
+
+    /// Jumps to the position after `n` moves. Returns `None` and stays put if there are fewer moves.
+    pub fn seek(&mut self, n: usize) -> Option<&Board> {
+        if n > self.len() {
+            return None;
+        }
+        self.index = n;
+        Some(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_through_game() {
+        let record = [((3, 1), "8/5 6/5"), ((4, 2), "8/4 6/4"), ((6, 5), "24/18 13/8")];
+        let moves = record.iter()
+            .map(|&((die1, die2), m)| (Dice::from_numbers(die1, die2), m.parse().unwrap()))
+            .collect::<Vec<_>>();
+        let mut replay = Replay::new(Board::new(), moves.clone()).unwrap();
+        assert_eq!(replay.len(), 3);
+        assert_eq!(*replay.current(), Board::new());
+        assert!(replay.step_back().is_none());
+
+        let mut expected = Board::new();
+        for &(dice, m) in &moves {
+            assert_eq!(replay.next_move(), Some((dice, m)));
+            expected.make_move(m, dice).unwrap();
+            assert_eq!(replay.step_forward(), Some(&expected));
+        }
+        assert!(replay.step_forward().is_none());
+        assert_eq!(replay.index(), 3);
+
+        while replay.step_back().is_some() {}
+        assert_eq!(replay.index(), 0);
+        assert_eq!(*replay.current(), Board::new());
+
+        assert!(replay.seek(4).is_none());
+        assert_eq!(replay.seek(3), Some(&expected));
+    }
+
+    #[test]
+    fn test_illegal_record() {
+        let moves = vec![(Dice::from_numbers(3, 1), "8/4".parse().unwrap())];
+        assert!(Replay::new(Board::new(), moves).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+/// Random keys for Zobrist hashing of a [`crate::backgammon::Board`].
+/// The keys are generated at compile time with splitmix64, so hashes are stable between runs.
+pub(crate) struct ZobristKeys {
+    /// Indexed by `[position][sign][count]`, sign 0 for the active and 1 for the passive player.
+    pub points: [[[u64; 16]; 2]; 24],
+    /// Indexed by `[player][count]`, player 0 for the active and 1 for the passive player.
+    pub bars: [[u64; 16]; 2],
+    /// Indexed by `[player][count]`, player 0 for the active and 1 for the passive player.
+    pub homes: [[u64; 16]; 2],
+    pub black_to_move: u64,
+}
+
+pub(crate) const ZOBRIST_KEYS: ZobristKeys = ZobristKeys::generate(0x9E37_79B9_7F4A_7C15);
+
+impl ZobristKeys {
+    const fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        let mut keys = ZobristKeys {
+            points: [[[0; 16]; 2]; 24],
+            bars: [[0; 16]; 2],
+            homes: [[0; 16]; 2],
+            black_to_move: 0,
+        };
+
+        let mut player = 0;
+        while player < 2 {
+            let mut count = 0;
+            while count < 16 {
+                let mut position = 0;
+                while position < 24 {
+                    keys.points[position][player][count] = splitmix64(&mut state);
+                    position += 1;
+                }
+                keys.bars[player][count] = splitmix64(&mut state);
+                keys.homes[player][count] = splitmix64(&mut state);
+                count += 1;
+            }
+            player += 1;
+        }
+        keys.black_to_move = splitmix64(&mut state);
+        keys
+    }
+}
+
+const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,116 @@
+//! Text interface for playing and analyzing games without the GUI.
+//! Reads one command per line from stdin, `help` lists the commands.
+
+use std::io::{self, BufRead, Write};
+
+use backgammon::{backgammon::{Dice, Game, GameOutcome, Move}, engine::find_best_legal_move, game};
+
+/// Search depth of the `hint` command.
+const HINT_DEPTH: u8 = 1;
+
+const HELP: &str = "\
+commands:
+  roll          roll the dice
+  dice <roll>   set the dice, e.g. dice 3/1
+  moves         list the legal moves
+  play <move>   play a move, e.g. play 24/18 13/11
+  undo          take back the last move
+  eval          evaluate the position for the player on roll
+  hint          show the best move for the current dice
+  board         print the board
+  quit          exit";
+
+fn main() {
+    let mut game = Game::new();
+    let mut stdout = io::stdout();
+    println!("type help for a list of commands");
+
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Failed to read from stdin");
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match command {
+            "" => continue,
+            "quit" | "exit" => break,
+            _ => println!("{}", execute(&mut game, command, args.trim())),
+        }
+        stdout.flush().expect("Failed to write to stdout");
+    }
+}
+
+fn execute(game: &mut Game, command: &str, args: &str) -> String {
+    match command {
+        "help" => HELP.to_string(),
+        "board" => {
+            let dice = game.dice().map_or("not rolled".to_string(), |dice| dice.to_string());
+            format!("{}\n{:?} to move, dice: {}", game.board().to_fancy_string(), game.active_player(), dice)
+        }
+        "roll" => match game.dice() {
+            Some(dice) => format!("already rolled {}", dice),
+            None => {
+                let dice = game.roll();
+                rolled(game, dice)
+            }
+        },
+        "dice" => match args.parse::<Dice>() {
+            Ok(dice) => {
+                game.set_dice(dice);
+                rolled(game, dice)
+            }
+            Err(e) => format!("invalid dice: {}", e),
+        },
+        "moves" => match game.dice() {
+            Some(dice) => game.board().generate_moves(dice)
+                .iter()
+                .enumerate()
+                .map(|(i, m)| format!("{:>3}. {}", i + 1, m.to_notation(game.board())))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => "roll the dice first".to_string(),
+        },
+        "play" => {
+            let m = match args.parse::<Move>() {
+                Ok(m) => m,
+                Err(e) => return format!("invalid move: {}", e),
+            };
+            let player = game.active_player();
+            let notation = m.to_notation(game.board());
+            match game.apply_move(m) {
+                Ok(()) => format!("{:?} played {}{}", player, notation, outcome(game)),
+                Err(e) => format!("illegal move: {}", e),
+            }
+        }
+        "undo" => if game.undo() { "took back the last move".to_string() } else { "nothing to undo".to_string() },
+        "eval" => {
+            let eval = game::Board::from(*game.board()).eval();
+            format!("eval for {:?}: {:.2}", game.active_player(), eval)
+        }
+        "hint" => match game.dice() {
+            Some(dice) => {
+                let best = find_best_legal_move(game.board(), dice, HINT_DEPTH);
+                format!("best move: {}", best.to_notation(game.board()))
+            }
+            None => "roll the dice first".to_string(),
+        },
+        _ => format!("unknown command {}, type help for a list of commands", command),
+    }
+}
+
+/// Announces the roll and passes the turn if it cannot be played.
+fn rolled(game: &mut Game, dice: Dice) -> String {
+    let player = game.active_player();
+    // All legal moves have the same length, the empty move is only legal if no checker can move.
+    let can_move = game.board().generate_moves(dice).first().is_some_and(|m| m.iter().next().is_some());
+    if can_move {
+        format!("{:?} rolled {}", player, dice)
+    } else {
+        game.apply_move(Move::new()).expect("The empty move is legal without moves");
+        format!("{:?} rolled {} and cannot move", player, dice)
+    }
+}
+
+fn outcome(game: &Game) -> String {
+    match game.board().outcome() {
+        GameOutcome::Ongoing => String::new(),
+        outcome => format!("\ngame over: {:?}", outcome),
+    }
+}
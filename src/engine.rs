@@ -1,75 +1,523 @@
-use std::{cell::{Cell, RefCell}, cmp::Reverse, f32::NEG_INFINITY, iter, rc::Rc};
+use std::{cell::{Cell, RefCell}, cmp::Reverse, collections::BTreeMap, f32::NEG_INFINITY, iter, rc::Rc, sync::{Arc, Mutex, atomic::{AtomicUsize, Ordering}}, time::{Duration, Instant}};
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
 use hashbrown::HashMap;
 
-use nannou::{prelude::Pow, rand::{random, seq::SliceRandom, thread_rng}};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use crate::{backgammon::{Match, opening_book}, game::{Board, Dice, GameOutcome, Move, Player}};
+
+/// Whether a stored evaluation is exact or only a bound, because the search was cut off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: u64,
+    depth: u8,
+    eval: f32,
+    bound: Bound,
+    /// Move that produced `eval`, followed to build the principal variation.
+    best: Move,
+}
+
+/// Fixed-size transposition table indexed by the Zobrist hash of a position and roll.
+/// Slots are locked individually, so a single table can be shared by all top-level moves
+/// searched in parallel. On collisions deeper entries are preferred.
+pub struct TranspositionTable {
+    entries: Vec<Mutex<Option<Entry>>>,
+    hits: AtomicUsize,
+}
+
+impl TranspositionTable {
+    pub const DEFAULT_CAPACITY: usize = 1 << 18;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a table sized for a search of `depth`. Shallow searches store few positions,
+    /// so they don't pay for allocating [`Self::DEFAULT_CAPACITY`] entries.
+    pub fn for_depth(depth: u8) -> Self {
+        Self::with_capacity((1 << (4 + 6 * depth.min(3) as usize)).min(Self::DEFAULT_CAPACITY))
+    }
+
+    /// Creates a table with a fixed number of entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "TranspositionTable needs at least one entry");
+        TranspositionTable {
+            entries: (0..capacity).map(|_| Mutex::new(None)).collect(),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Number of probes that were answered from the table.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn key(board: &Board, dice: Dice) -> u64 {
+        let (a, b) = match dice {
+            Dice::Double { value, .. } => (value, value),
+            Dice::Single { value_1, value_2, .. } => (value_1, value_2),
+        };
+        board.zobrist_hash() ^ ((a as u64) << 3 | b as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
 
-use crate::game::{Board, Dice, GameOutcome, Move, Player};
+    fn slot(&self, key: u64) -> &Mutex<Option<Entry>> {
+        &self.entries[(key % self.entries.len() as u64) as usize]
+    }
+
+    /// Returns a stored evaluation that is usable for a search of `depth` in the window `alpha..beta`.
+    fn probe(&self, key: u64, depth: u8, alpha: f32, beta: f32) -> Option<f32> {
+        let entry = (*self.slot(key).lock().unwrap())?;
+        if entry.key != key || entry.depth < depth {
+            return None;
+        }
+        let usable = match entry.bound {
+            Bound::Exact => true,
+            Bound::Lower => entry.eval >= beta,
+            Bound::Upper => entry.eval <= alpha,
+        };
+        if usable {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Some(entry.eval)
+        } else {
+            None
+        }
+    }
+
+    /// Best move recorded for `board` and `dice`, if the position is still stored.
+    fn best_move(&self, board: &Board, dice: Dice) -> Option<Move> {
+        let key = Self::key(board, dice);
+        self.slot(key).lock().unwrap().filter(|entry| entry.key == key).map(|entry| entry.best)
+    }
+
+    fn store(&self, entry: Entry) {
+        let mut slot = self.slot(entry.key).lock().unwrap();
+        match *slot {
+            Some(existing) if existing.key != entry.key && existing.depth > entry.depth => (),
+            _ => *slot = Some(entry),
+        }
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bounded cache of generated move lists keyed by [`Board::legal_move_fingerprint`], so searches
+/// that reach the same position with the same roll again skip move generation. Holds at most
+/// `capacity` lists and evicts the least recently used one when full. Shared by parallel searches,
+/// which get the cached lists without copying them. Lists are stored in search order, moves with
+/// the highest [`Board::captured_value`] first.
+pub struct MoveCache {
+    inner: Mutex<MoveCacheInner>,
+    capacity: usize,
+    hits: AtomicUsize,
+}
+
+/// Key returned by [`Board::legal_move_fingerprint`].
+type Fingerprint = (u128, Dice);
+
+struct MoveCacheInner {
+    /// Move list and last use of every cached position.
+    entries: HashMap<Fingerprint, (Arc<[Move]>, u64)>,
+    /// Cached positions by their last use, oldest first.
+    recency: BTreeMap<u64, Fingerprint>,
+    clock: u64,
+}
+
+impl MoveCache {
+    pub const DEFAULT_CAPACITY: usize = 1 << 14;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a cache holding at most `capacity` move lists.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "MoveCache needs at least one entry");
+        MoveCache {
+            inner: Mutex::new(MoveCacheInner { entries: HashMap::new(), recency: BTreeMap::new(), clock: 0 }),
+            capacity,
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of move lists currently stored.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups that were answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The moves of [`Board::generate_moves`], highest [`Board::captured_value`] first, taken
+    /// from the cache if present.
+    pub fn generate_moves(&self, board: &Board, dice: Dice) -> Arc<[Move]> {
+        let key = board.legal_move_fingerprint(dice);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            let MoveCacheInner { entries, recency, clock } = &mut *inner;
+            if let Some((moves, last_use)) = entries.get_mut(&key) {
+                recency.remove(last_use);
+                *clock += 1;
+                *last_use = *clock;
+                recency.insert(*clock, key);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Arc::clone(moves);
+            }
+        }
+
+        // Generated without holding the lock, another thread may store the same list meanwhile.
+        let mut moves = board.generate_moves(dice);
+        order_moves(board, &mut moves);
+        let moves: Arc<[Move]> = moves.into();
+        let mut inner = self.inner.lock().unwrap();
+        let MoveCacheInner { entries, recency, clock } = &mut *inner;
+        if !entries.contains_key(&key) {
+            if entries.len() >= self.capacity && let Some((_, oldest)) = recency.pop_first() {
+                entries.remove(&oldest);
+            }
+            *clock += 1;
+            entries.insert(key, (Arc::clone(&moves), *clock));
+            recency.insert(*clock, key);
+        }
+        moves
+    }
+}
+
+impl Default for MoveCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sorts `moves` by [`Board::captured_value`], highest first. Hitting moves tend to be strong,
+/// searching them first produces earlier cutoffs. The sort is stable, so moves capturing the same
+/// value keep the order of [`Board::generate_moves`].
+fn order_moves(board: &Board, moves: &mut [Move]) {
+    moves.sort_by_key(|m| Reverse(board.captured_value(m)));
+}
 
 pub fn find_best_move(board: &Board, dice: Dice, depth: u8) -> Move {
+    if let Some(m) = opening_book_move(board, dice) {
+        return m;
+    }
+    analyze(board, dice, depth)
+        .first()
+        .map(|&(m, _)| m)
+        .expect("No moves available")
+}
+
+/// Play from the [`opening_book`] for the first move of a game started by White.
+fn opening_book_move(board: &Board, dice: Dice) -> Option<Move> {
+    let Dice::Single { value_1, value_2, .. } = dice else {
+        return None;
+    };
+    if *board != Board::new() {
+        return None;
+    }
+    opening_book::lookup(crate::backgammon::Dice::from_numbers(value_1, value_2)).map(Move::from)
+}
+
+/// Evaluates every legal move, sorted descending by evaluation.
+/// Evaluations are from the perspective of the moving player, higher is better.
+pub fn analyze(board: &Board, dice: Dice, depth: u8) -> Vec<(Move, f32)> {
+    analyze_with(board, dice, depth, &TranspositionTable::for_depth(depth), None)
+}
+
+/// [`analyze`] with a table owned by the caller, which can be reused across calls,
+/// e.g. for consecutive positions of a game.
+pub fn analyze_with_table(board: &Board, dice: Dice, depth: u8, seen: &TranspositionTable) -> Vec<(Move, f32)> {
+    analyze_with(board, dice, depth, seen, None)
+}
+
+/// [`find_best_move`] reusing the move lists stored in `cache`, which can be shared between searches.
+/// Returns the same move as [`find_best_move`].
+pub fn find_best_move_cached(board: &Board, dice: Dice, depth: u8, cache: &MoveCache) -> Move {
+    if let Some(m) = opening_book_move(board, dice) {
+        return m;
+    }
+    analyze_with(board, dice, depth, &TranspositionTable::for_depth(depth), Some(cache))
+        .first()
+        .map(|&(m, _)| m)
+        .expect("No moves available")
+}
+
+/// [`find_best_move`] for a position of the bitboard [`crate::backgammon::Board`], which only
+/// returns moves of [`crate::backgammon::Board::generate_moves`]. The engine's generator also
+/// allows some bear-offs the bitboard rules forbid, those are skipped for the next best move.
+pub fn find_best_legal_move(board: &crate::backgammon::Board, dice: crate::backgammon::Dice, depth: u8) -> crate::backgammon::Move {
+    let legal_moves = board.generate_moves(dice);
+    let legal = |m: Move| Some(crate::backgammon::Move::from(m)).filter(|m| legal_moves.contains(m));
+    let engine_board = Board::from(*board);
+    let (low, high) = dice.canonical();
+    let engine_dice = Dice::new(high, low);
+    opening_book_move(&engine_board, engine_dice).and_then(legal)
+        .or_else(|| analyze(&engine_board, engine_dice, depth).into_iter().find_map(|(m, _)| legal(m)))
+        .unwrap_or(legal_moves[0])
+}
+
+fn analyze_with(board: &Board, dice: Dice, depth: u8, seen: &TranspositionTable, moves: Option<&MoveCache>) -> Vec<(Move, f32)> {
     let legal_moves = board.generate_moves(dice);
     
     if legal_moves.is_empty() {
         panic!("No legal moves available");
     }
 
-    let evals = legal_moves.into_par_iter()
+    let mut evals = legal_moves.into_par_iter()
         .map(|m| {
             let mut new_board = board.clone();
             new_board.make_move_unchecked(m);
-            let mut seen = HashMap::new();
-            let eval = -alpha_beta(&new_board, depth, f32::NEG_INFINITY, f32::INFINITY, dice, &mut seen);
+            let eval = -alpha_beta(&new_board, depth, f32::NEG_INFINITY, f32::INFINITY, dice, Search { moves, ..Search::new(seen) })
+                .expect("Search without deadline cannot time out");
             (m, eval)
         })
         .collect::<Vec<_>>();
-        
+
+    evals.sort_by(|(_, eval1), (_, eval2)| eval2.partial_cmp(eval1).unwrap());
+    evals
+}
+
+/// Finds the best move together with the principal variation, the expected line of play
+/// following it. Each entry holds the roll assumed for that ply and the best move for it.
+/// Since rolls are random, the line assumes the most probable roll at every chance node.
+/// The line is read from the best moves the search recorded in its transposition table,
+/// so it is the line the root search scored. The variation contains at most `depth` moves.
+pub fn find_best_move_pv(board: &Board, dice: Dice, depth: u8) -> (Move, Vec<(Dice, Move)>) {
+    let seen = TranspositionTable::for_depth(depth);
+    let evals = analyze_with(board, dice, depth, &seen, None);
+    let best_move = opening_book_move(board, dice).unwrap_or(evals[0].0);
+
+    let mut pv = Vec::new();
+    let mut current = *board;
+    current.make_move_unchecked(best_move);
+    // Mirrors `analyze`, which searches the reply with the same dice.
+    let mut node_dice = dice;
+    while pv.len() < depth as usize && current.outcome() == GameOutcome::Ongoing {
+        let Some(m) = seen.best_move(&current, node_dice) else {
+            break;
+        };
+        pv.push((node_dice, m));
+        current.make_move_unchecked(m);
+        node_dice = most_probable_roll();
+    }
+    (best_move, pv)
+}
+
+/// Renders a principal variation in standard notation, e.g. `"3/1: 8/5 6/5 | 6/5: 24/13"`.
+pub fn pv_to_string(pv: &[(Dice, Move)]) -> String {
+    pv.iter()
+        .map(|(dice, m)| format!("{}: {}", dice.to_string(), crate::backgammon::Move::from(*m)))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn most_probable_roll() -> Dice {
+    Dice::ALL_WITH_PROPABILITY
+        .into_iter()
+        .reduce(|best, roll| if roll.1 > best.1 { roll } else { best })
+        .map(|(dice, _)| dice)
+        .expect("There is at least one roll")
+}
+
+/// Iterative deepening version of [`find_best_move`] bounded by a wall-clock budget.
+/// Searches at depth 0, 1, 2, ... reusing the transposition table between iterations,
+/// and returns the best move of the deepest fully completed iteration together with that depth.
+pub fn find_best_move_timed(board: &Board, dice: Dice, budget: Duration) -> (Move, u8) {
+    let deadline = Instant::now() + budget;
+    let legal_moves = board.generate_moves(dice);
+
+    if legal_moves.is_empty() {
+        panic!("No legal moves available");
+    }
+
+    let seen = TranspositionTable::new();
+    let mut best = (legal_moves[0], 0);
+
+    for depth in 0..=u8::MAX {
+        // Depth 0 is a static evaluation and always completes, so there is always a result.
+        let iteration_deadline = if depth == 0 { None } else { Some(deadline) };
+        let evals = legal_moves.par_iter()
+            .map(|&m| {
+                let mut new_board = *board;
+                new_board.make_move_unchecked(m);
+                alpha_beta(&new_board, depth, f32::NEG_INFINITY, f32::INFINITY, dice, Search { deadline: iteration_deadline, ..Search::new(&seen) })
+                    .map(|eval| (m, -eval))
+            })
+            .collect::<Option<Vec<_>>>();
+
+        // A partially completed iteration is discarded.
+        let Some(evals) = evals else {
+            break;
+        };
         let best_move = evals.into_iter()
             .max_by(|(_, eval1), (_, eval2)| eval1.partial_cmp(eval2).unwrap())
             .expect("No moves available");
-        best_move.0
+        best = (best_move.0, depth);
+
+        if Instant::now() >= deadline {
+            break;
+        }
+    }
+    best
+}
+
+/// Expectiminimax value of `board` for the player on roll with `dice`, searching `plies`
+/// moves deep. Decision nodes take the best move, chance nodes average over all rolls
+/// weighted by their probability. No pruning is done, so the result is exact for the depth.
+pub fn expectiminimax(board: &Board, dice: Dice, plies: u8) -> f32 {
+    if plies == 0 || board.outcome() != GameOutcome::Ongoing {
+        return board.eval();
+    }
+
+    board.generate_moves(dice)
+        .into_iter()
+        .map(|m| {
+            let mut new_board = *board;
+            new_board.make_move_unchecked(m);
+            -expected_value(&new_board, plies - 1)
+        })
+        .fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// Chance node of [`expectiminimax`], from the perspective of the player about to roll.
+fn expected_value(board: &Board, plies: u8) -> f32 {
+    if plies == 0 || board.outcome() != GameOutcome::Ongoing {
+        return board.eval();
+    }
+
+    let (sum, total_probability) = Dice::ALL.iter()
+        .map(|&dice| (expectiminimax(board, dice, plies) * dice.probability(), dice.probability()))
+        .fold((0.0, 0.0), |(sum, total), (value, probability)| (sum + value, total + probability));
+    sum / total_probability
+}
+
+/// Picks the move maximizing the expected value over the opponent's next roll: every move is
+/// scored by averaging the opponent's best reply, searched with [`expectiminimax`] for `plies`
+/// moves, over all 21 rolls weighted by their probability. With `plies == 1` this is a plain
+/// one-ply-plus-chance search. Unlike [`find_best_move`] the top level is exact, no pruning is done.
+pub fn best_move_expectimax(board: &Board, dice: Dice, plies: u8) -> Move {
+    board.generate_moves(dice)
+        .into_par_iter()
+        .map(|m| {
+            let mut new_board = *board;
+            new_board.make_move_unchecked(m);
+            (m, -expected_value(&new_board, plies))
+        })
+        .max_by(|(_, eval1), (_, eval2)| eval1.partial_cmp(eval2).unwrap())
+        .map(|(m, _)| m)
+        .expect("No moves available")
 }
 
 pub fn search_eval(board: &Board, depth: u8) -> f32 {
-    let mut seen = HashMap::new();
-    average_eval(board, f32::NEG_INFINITY, f32::INFINITY, depth, &mut seen)
+    let seen = TranspositionTable::for_depth(depth);
+    average_eval(board, f32::NEG_INFINITY, f32::INFINITY, depth, Search::new(&seen))
+        .expect("Search without deadline cannot time out")
+}
+
+/// State shared by all nodes of a search.
+#[derive(Clone, Copy)]
+struct Search<'a> {
+    seen: &'a TranspositionTable,
+    /// Opt-in cache for the move lists of the decision nodes.
+    moves: Option<&'a MoveCache>,
+    deadline: Option<Instant>,
+    /// Counts the visited nodes, including leaves, when set.
+    nodes: Option<&'a AtomicUsize>,
+    /// Search hitting moves first, disabled only to measure its effect.
+    move_ordering: bool,
 }
 
-fn average_eval(board: &Board, alpha: f32, beta: f32, depth: u8, seen: &mut HashMap<(Board,Dice), f32>) -> f32 {
+impl<'a> Search<'a> {
+    fn new(seen: &'a TranspositionTable) -> Self {
+        Search { seen, moves: None, deadline: None, nodes: None, move_ordering: true }
+    }
+}
+
+/// Returns `None` if the deadline passed before the search completed.
+fn average_eval(board: &Board, alpha: f32, beta: f32, depth: u8, search: Search) -> Option<f32> {
     let mut sum = 0.0;
     for (dice, propability) in Dice::ALL_WITH_PROPABILITY {
-        let eval = alpha_beta(board, depth, alpha, beta, dice, seen);
+        let eval = alpha_beta(board, depth, alpha, beta, dice, search)?;
         sum += eval * propability;
     }
-    sum 
+    Some(sum)
 }
 
-fn alpha_beta(board: &Board, depth: u8, mut alpha: f32, beta: f32, dice: Dice, seen: &mut HashMap<(Board,Dice), f32>) -> f32 {
+/// Returns `None` if the deadline passed before the search completed.
+fn alpha_beta(board: &Board, depth: u8, mut alpha: f32, beta: f32, dice: Dice, search: Search) -> Option<f32> {
+    let seen = search.seen;
+    if let Some(nodes) = search.nodes {
+        nodes.fetch_add(1, Ordering::Relaxed);
+    }
     if depth == 0 {
-        return board.eval();
+        return Some(board.eval());
+    }
+
+    let key = TranspositionTable::key(board, dice);
+    if let Some(cached_eval) = seen.probe(key, depth, alpha, beta) {
+        return Some(cached_eval);
     }
 
-    if let Some(&cached_eval) = seen.get(&(*board, dice)) {
-        return cached_eval;
+    if search.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        return None;
     }
     
-    let legal_moves = board.generate_moves(dice);
+    // Cached lists are already ordered and are searched in place, without copying them.
+    let (cached, mut generated);
+    let legal_moves: &[Move] = match search.moves {
+        Some(cache) if search.move_ordering => {
+            cached = cache.generate_moves(board, dice);
+            &cached
+        }
+        _ => {
+            generated = board.generate_moves(dice);
+            if search.move_ordering {
+                order_moves(board, &mut generated);
+            }
+            &generated
+        }
+    };
     if legal_moves.is_empty() {
-        return board.eval();
+        return Some(board.eval());
     }
 
-    // legal_moves.sort_unstable_by_key(
-    //     |m| std::cmp::Reverse(board.captured_value(&m))
-    // );
-
+    let alpha_orig = alpha;
     let mut best_eval = f32::NEG_INFINITY;
+    let mut best = legal_moves[0];
 
-    for m in legal_moves {
+    for &m in legal_moves {
         let mut new_board = board.clone();
         new_board.make_move_unchecked(m);
-        let eval = -average_eval(&new_board, -beta, -alpha, depth - 1, seen);
+        let eval = -average_eval(&new_board, -beta, -alpha, depth - 1, search)?;
         
-        best_eval = best_eval.max(eval);
+        if eval > best_eval {
+            best_eval = eval;
+            best = m;
+        }
         alpha = alpha.max(best_eval);
 
         if beta <= alpha {
@@ -77,26 +525,39 @@ fn alpha_beta(board: &Board, depth: u8, mut alpha: f32, beta: f32, dice: Dice, s
         }
     }
 
-    seen.insert((*board, dice), best_eval);
-    best_eval
+    let bound = if best_eval <= alpha_orig {
+        Bound::Upper
+    } else if best_eval >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    seen.store(Entry { key, depth, eval: best_eval, bound, best });
+    Some(best_eval)
 }
 
-pub fn monte_carlo_search(board: &Board, dice: Dice, simulations: usize, depth: usize) -> Move {
+/// Picks the move with the best average evaluation after `simulations` rollouts of `depth` plies.
+/// The result only depends on the state of `rng`, so a seeded generator gives reproducible moves.
+pub fn monte_carlo_search(board: &Board, dice: Dice, simulations: usize, depth: usize, rng: &mut impl Rng) -> Move {
     let legal_moves = board.generate_moves(dice);
 
     if legal_moves.is_empty() {
         panic!("No legal moves available");
     }
 
+    // Moves are simulated in parallel, each with its own generator seeded from `rng`.
+    let seeds = legal_moves.iter().map(|_| rng.random()).collect::<Vec<u64>>();
     legal_moves.into_par_iter() 
-        .map(|m| {
+        .zip(seeds.into_par_iter())
+        .map(|(m, seed)| {
+            let mut rng = StdRng::seed_from_u64(seed);
             let mut new_board = board.clone();
             new_board.make_move_unchecked(m.clone());
             let mut score = 0.0;
             for _ in 0..simulations {
                 match board.get_active_player() {
-                    Player::White => score += simulate_random_game(&new_board, depth),
-                    Player::Black => score -= simulate_random_game(&new_board, depth),
+                    Player::White => score += simulate_random_game(&new_board, depth, &mut rng),
+                    Player::Black => score -= simulate_random_game(&new_board, depth, &mut rng),
                 }
             }
             (m, score / simulations as f32)
@@ -108,36 +569,51 @@ pub fn monte_carlo_search(board: &Board, dice: Dice, simulations: usize, depth:
         .expect("No moves available")
 }
 
-fn simulate_random_game(board: &Board, depth: usize) -> f32 {
+fn simulate_random_game(board: &Board, depth: usize, rng: &mut impl Rng) -> f32 {
     let mut current_board = board.clone();
 
     for _ in 0..depth { 
-        let dice = Dice::roll();
+        let dice = Dice::roll_with(rng);
 
         if GameOutcome::Ongoing != current_board.outcome() {
             break; 
         } 
-        let m = find_highest_eval_move(&current_board, dice);
+        let m = find_highest_eval_move(&current_board, dice, rng);
         
         // let m = find_highest_eval_move(&current_board, dice);
         
         current_board.make_move_unchecked(m);
     }
     
-    current_board.eval_absolute()
+    rollout_reward(&current_board, Player::White)
+}
+
+/// Value of the position where a rollout stopped for `player`, see [`Board::eval_normalized`]:
+/// finished games are worth 1, 2 or 3 for a win, gammon or backgammon, positions still in
+/// play are scaled into `(-1, 1)`. Losses are negative.
+fn rollout_reward(board: &Board, player: Player) -> f32 {
+    let reward = board.eval_normalized();
+    if board.active_player() == player { reward } else { -reward }
 }
 
-fn choose_random_move(board: &Board, dice: Dice) -> Move {
+fn choose_random_move(board: &Board, dice: Dice, rng: &mut impl Rng) -> Move {
     let legal_moves = board.generate_moves(dice);
     
     if legal_moves.is_empty() {
         panic!("No legal moves available");
     }
 
-    *legal_moves.choose(&mut thread_rng()).expect("No moves available")
+    *legal_moves.choose(rng).expect("No moves available")
 }
 
-fn find_highest_eval_move(board: &Board, dice: Dice) -> Move {
+fn find_highest_eval_move(board: &Board, dice: Dice, rng: &mut impl Rng) -> Move {
+    eval_ranked_move(board, dice, 16, false, rng)
+}
+
+/// Sorts the legal moves by the static evaluation, best first or with `worst_first` worst first,
+/// and picks the move at index `r^exponent * (len - 1)` for a uniform `r` in `[0, 1)`.
+/// The larger the exponent, the more likely the first move in the order is played.
+fn eval_ranked_move(board: &Board, dice: Dice, exponent: i32, worst_first: bool, rng: &mut impl Rng) -> Move {
     let legal_moves = board.generate_moves(dice);
     
     if legal_moves.is_empty() {
@@ -147,7 +623,7 @@ fn find_highest_eval_move(board: &Board, dice: Dice) -> Move {
     // legal_moves.shuffle(&mut thread_rng());
     let len = legal_moves.len();
     
-    let indx = (random::<f32>().pow(16) * (len as f32 - 1.0)) as usize; 
+    let indx = (rng.random::<f32>().powi(exponent) * (len as f32 - 1.0)) as usize; 
     // println!("Choosing move at index: {}\\{}", indx, len);
 
     let mut evals = legal_moves.into_iter()
@@ -158,7 +634,11 @@ fn find_highest_eval_move(board: &Board, dice: Dice) -> Move {
         })
         .collect::<Vec<_>>();
 
+    // `eval` of the new board is from the opponent's perspective, so ascending is best first.
     evals.sort_unstable_by(|(_, eval1), (_, eval2)| eval1.partial_cmp(&eval2).unwrap());
+    if worst_first {
+        evals.reverse();
+    }
     evals
         .into_iter()
         .nth(indx)
@@ -166,6 +646,404 @@ fn find_highest_eval_move(board: &Board, dice: Dice) -> Move {
         .expect("No moves available")
 }
 
+/// Bot strength for [`choose_move_with_difficulty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    /// Plays a random move, weighted toward the moves with the worst static evaluation.
+    Beginner,
+    /// Plays the best move of a depth 1 search.
+    Intermediate,
+    /// Plays perfectly in bear-offs using the bear-off database, otherwise like [`find_best_move`]
+    /// at depth 2, including the opening book.
+    Expert,
+}
+
+/// Exponent of the index trick of [`eval_ranked_move`] for [`Difficulty::Beginner`]. With 2 the worst
+/// move is the most likely pick, but every move is played regularly.
+const BEGINNER_EXPONENT: i32 = 2;
+
+/// Chooses a move for a bot of the given strength, see [`Difficulty`] for what each level plays.
+pub fn choose_move_with_difficulty(board: &Board, dice: Dice, level: Difficulty) -> Move {
+    match level {
+        Difficulty::Beginner => eval_ranked_move(board, dice, BEGINNER_EXPONENT, true, &mut rand::rng()),
+        Difficulty::Intermediate => find_best_move(board, dice, 1),
+        Difficulty::Expert => bearoff_move(board, dice).unwrap_or_else(|| find_best_move(board, dice, 2)),
+    }
+}
+
+/// Move with the highest cubeless equity from the bear-off database, or `None` if the position
+/// is not a bear-off of both players.
+fn bearoff_move(board: &Board, dice: Dice) -> Option<Move> {
+    crate::backgammon::Board::from(*board).bearoff_equity()?;
+    let equity = |m: &Move| {
+        let mut new_board = *board;
+        new_board.make_move_unchecked(*m);
+        match new_board.outcome() {
+            GameOutcome::Ongoing => -crate::backgammon::Board::from(new_board).bearoff_equity()
+                .expect("A bear-off stays a bear-off"),
+            _ => 1.0,
+        }
+    };
+    board.generate_moves(dice)
+        .into_iter()
+        .map(|m| (equity(&m), m))
+        .max_by(|(eval1, _), (eval2, _)| eval1.total_cmp(eval2))
+        .map(|(_, m)| m)
+}
+
+/// A player for [`play_match`].
+pub trait Bot {
+    /// Chooses one of the legal moves of the active player for `dice`.
+    fn choose_move(&self, board: &Board, dice: Dice) -> Move;
+}
+
+/// Plays a uniformly random legal move.
+pub struct RandomBot {
+    rng: RefCell<StdRng>,
+}
+
+impl RandomBot {
+    /// Creates a bot whose choices are reproducible for the same `seed`.
+    pub fn new(seed: u64) -> Self {
+        RandomBot { rng: RefCell::new(StdRng::seed_from_u64(seed)) }
+    }
+}
+
+impl Bot for RandomBot {
+    fn choose_move(&self, board: &Board, dice: Dice) -> Move {
+        choose_random_move(board, dice, &mut *self.rng.borrow_mut())
+    }
+}
+
+/// Plays [`Board::greedy_move`] without searching.
+pub struct GreedyBot;
+
+impl Bot for GreedyBot {
+    fn choose_move(&self, board: &Board, dice: Dice) -> Move {
+        board.greedy_move(dice)
+    }
+}
+
+/// Plays [`find_best_move`] searching `depth` plies.
+pub struct SearchBot {
+    pub depth: u8,
+}
+
+impl Bot for SearchBot {
+    fn choose_move(&self, board: &Board, dice: Dice) -> Move {
+        find_best_move(board, dice, self.depth)
+    }
+}
+
+/// Results of [`play_match`]. Counts are indexed by White and Black.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchStats {
+    /// Games won with a single game.
+    pub wins: [usize; 2],
+    pub gammons: [usize; 2],
+    pub backgammons: [usize; 2],
+    /// Games aborted with a [`crate::game::SelfPlayError`]: they did not end within
+    /// `MAX_PLAYOUT_PLIES` turns or got stuck with neither player able to move.
+    pub unfinished: usize,
+}
+
+impl MatchStats {
+    /// Number of games played, including the unfinished ones.
+    pub fn games(&self) -> usize {
+        self.total_wins(Player::White) + self.total_wins(Player::Black) + self.unfinished
+    }
+
+    /// Games won by `player` with a single game, a gammon or a backgammon.
+    pub fn total_wins(&self, player: Player) -> usize {
+        let index = player as usize;
+        self.wins[index] + self.gammons[index] + self.backgammons[index]
+    }
+
+    /// Points won by `player`, counting gammons double and backgammons triple.
+    pub fn points(&self, player: Player) -> usize {
+        let index = player as usize;
+        self.wins[index] + 2 * self.gammons[index] + 3 * self.backgammons[index]
+    }
+}
+
+/// Plays `games` games from the starting position between two bots, White always opening.
+/// The dice are rolled from `seed`, so deterministic bots produce the same results for the same seed.
+pub fn play_match(white: &dyn Bot, black: &dyn Bot, games: usize, seed: u64) -> MatchStats {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut stats = MatchStats::default();
+    for _ in 0..games {
+        let choose_move = |board: &Board, dice: Dice| match board.active_player() {
+            Player::White => white.choose_move(board, dice),
+            Player::Black => black.choose_move(board, dice),
+        };
+        match crate::game::play_game(choose_move, MAX_PLAYOUT_PLIES, &mut rng) {
+            Ok(GameOutcome::Win(player)) => stats.wins[player as usize] += 1,
+            Ok(GameOutcome::Gammon(player)) => stats.gammons[player as usize] += 1,
+            Ok(GameOutcome::Backgammon(player)) => stats.backgammons[player as usize] += 1,
+            Ok(GameOutcome::Ongoing) => unreachable!("play_game only returns finished games"),
+            Err(_) => stats.unfinished += 1,
+        }
+    }
+    stats
+}
+
+/// Safety limit for games played out to the end, which in practice always finish much earlier.
+const MAX_PLAYOUT_PLIES: usize = 1000;
+/// Number of games played out by [`cube_decision`] and [`take_decision`].
+const CUBE_SIMULATIONS: usize = 1000;
+/// Minimum winning chance of the doubling player to offer a double.
+const DOUBLE_POINT: f32 = 0.68;
+/// Minimum winning chance of the doubled player to accept a double.
+const TAKE_POINT: f32 = 0.25;
+/// The cube can not be turned beyond this value.
+const MAX_CUBE_VALUE: u8 = 64;
+/// Share of games won with a gammon, used to estimate the match winning chances.
+const GAMMON_RATE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeAction {
+    Double,
+    NoDouble,
+    Take,
+    Pass,
+}
+
+/// Outcome probabilities of a position from the perspective of one player.
+/// Gammons include backgammons and wins include both, so `backgammon <= gammon <= win`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Probabilities {
+    pub win: f32,
+    pub gammon: f32,
+    pub backgammon: f32,
+}
+
+impl Probabilities {
+    fn from_outcomes(outcomes: impl Iterator<Item = GameOutcome>, player: Player, simulations: usize) -> Self {
+        let mut counts = [0usize; 3];
+        for outcome in outcomes {
+            match outcome {
+                GameOutcome::Win(winner) if winner == player => counts[0] += 1,
+                GameOutcome::Gammon(winner) if winner == player => {
+                    counts[0] += 1;
+                    counts[1] += 1;
+                }
+                GameOutcome::Backgammon(winner) if winner == player => counts.iter_mut().for_each(|c| *c += 1),
+                _ => (),
+            }
+        }
+        let n = simulations.max(1) as f32;
+        Probabilities {
+            win: counts[0] as f32 / n,
+            gammon: counts[1] as f32 / n,
+            backgammon: counts[2] as f32 / n,
+        }
+    }
+}
+
+/// Plays `simulations` games for every legal move and returns the outcome probabilities
+/// for the moving player. Rollouts still running after `depth` plies are awarded to the
+/// player ahead in the race.
+pub fn monte_carlo_probabilities(board: &Board, dice: Dice, simulations: usize, depth: usize, rng: &mut impl Rng) -> Vec<(Move, Probabilities)> {
+    let legal_moves = board.generate_moves(dice);
+
+    if legal_moves.is_empty() {
+        panic!("No legal moves available");
+    }
+
+    let player = board.get_active_player();
+    let seeds = legal_moves.iter().map(|_| rng.random()).collect::<Vec<u64>>();
+    legal_moves.into_par_iter()
+        .zip(seeds.into_par_iter())
+        .map(|(m, seed)| {
+            let mut new_board = *board;
+            new_board.make_move_unchecked(m);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let outcomes = (0..simulations).map(|_| play_out(&new_board, depth, &mut rng));
+            (m, Probabilities::from_outcomes(outcomes, player, simulations))
+        })
+        .collect()
+}
+
+/// Estimates the probability that the player on roll wins the game by playing out
+/// `simulations` games with the rollout policy.
+pub fn win_probability(board: &Board, simulations: usize, rng: &mut impl Rng) -> f32 {
+    game_probabilities(board, simulations, rng).win
+}
+
+/// Outcome probabilities of the player on roll, estimated like [`win_probability`].
+pub fn game_probabilities(board: &Board, simulations: usize, rng: &mut impl Rng) -> Probabilities {
+    let player = board.get_active_player();
+    let seeds = (0..simulations).map(|_| rng.random()).collect::<Vec<u64>>();
+    let outcomes = seeds.into_par_iter()
+        .map(|seed| play_out(board, MAX_PLAYOUT_PLIES, &mut StdRng::seed_from_u64(seed)))
+        .collect::<Vec<_>>();
+    Probabilities::from_outcomes(outcomes.into_iter(), player, simulations)
+}
+
+/// Plays the game to the end or for at most `max_plies`. Unfinished games are resolved
+/// as a single win for the player with the lower pip count, counting the player on roll
+/// as a roll ahead.
+fn play_out(board: &Board, max_plies: usize, rng: &mut impl Rng) -> GameOutcome {
+    let mut current_board = *board;
+    for _ in 0..max_plies {
+        if current_board.outcome() != GameOutcome::Ongoing {
+            return current_board.outcome();
+        }
+        let m = find_highest_eval_move(&current_board, Dice::roll_with(rng), rng);
+        current_board.make_move_unchecked(m);
+    }
+
+    match current_board.outcome() {
+        GameOutcome::Ongoing => {
+            let active = current_board.active_player();
+            // Being on roll is worth about 4 pips, half of an average roll.
+            if current_board.pip_count(active) <= current_board.pip_count(active.opposite()) + 4 {
+                GameOutcome::Win(active)
+            } else {
+                GameOutcome::Win(active.opposite())
+            }
+        }
+        outcome => outcome,
+    }
+}
+
+/// Decides whether the player on roll should double a cube currently at `cube_value`.
+/// Doubles inside the doubling window: the winning chance is at least `DOUBLE_POINT`, but
+/// the player is not too good to double, i.e. playing on for a gammon while the opponent would
+/// pass is worth more than the single point of the pass.
+pub fn cube_decision(board: &Board, cube_value: u8, rng: &mut impl Rng) -> CubeAction {
+    if cube_value >= MAX_CUBE_VALUE {
+        return CubeAction::NoDouble;
+    }
+    let p = game_probabilities(board, CUBE_SIMULATIONS, rng);
+    if p.win < DOUBLE_POINT {
+        return CubeAction::NoDouble;
+    }
+    // Cubeless equity of playing on, the gammons of the opponent are negligible this far ahead.
+    let equity = p.win + p.gammon + p.backgammon - (1.0 - p.win);
+    if 1.0 - p.win < TAKE_POINT && equity > 1.0 {
+        CubeAction::NoDouble
+    } else {
+        CubeAction::Double
+    }
+}
+
+/// Decides whether the opponent of the player on roll should accept a double of a cube
+/// currently at `cube_value`. Taking risks `2 * cube_value` while passing loses `cube_value`,
+/// so a take is correct as long as the winning chance is above the take point.
+pub fn take_decision(board: &Board, cube_value: u8, rng: &mut impl Rng) -> CubeAction {
+    if cube_value >= MAX_CUBE_VALUE {
+        return CubeAction::Pass;
+    }
+    if 1.0 - win_probability(board, CUBE_SIMULATIONS, rng) >= TAKE_POINT {
+        CubeAction::Take
+    } else {
+        CubeAction::Pass
+    }
+}
+
+/// Match play version of [`cube_decision`] for the cube of the current game of `score`.
+/// The doubling window is shifted by the take point of the opponent at this score. If the
+/// opponent could not use the cube after taking, the player on roll doubles as soon as
+/// doubling gains match winning chances, e.g. the trailer after the Crawford game.
+pub fn match_cube_decision(board: &Board, score: &Match, rng: &mut impl Rng) -> CubeAction {
+    if !score.doubling_allowed() || score.cube() >= MAX_CUBE_VALUE {
+        return CubeAction::NoDouble;
+    }
+    let (away, opponent_away, crawford_done) = away_scores(board, score);
+    let cube = score.cube() as i16;
+    let p = win_probability(board, CUBE_SIMULATIONS, rng);
+
+    let double = if 2 * cube >= opponent_away {
+        let equity = |won: i16, lost: i16| {
+            p * match_equity(away - won, opponent_away, crawford_done)
+                + (1.0 - p) * match_equity(away, opponent_away - lost, crawford_done)
+        };
+        let pass = match_equity(away - cube, opponent_away, crawford_done);
+        equity(2 * cube, 2 * cube).min(pass) > equity(cube, cube)
+    } else {
+        let take_point = match_take_point(opponent_away, away, cube, crawford_done);
+        p >= 1.0 - take_point - (1.0 - DOUBLE_POINT - TAKE_POINT)
+    };
+    if double {
+        CubeAction::Double
+    } else {
+        CubeAction::NoDouble
+    }
+}
+
+/// Match play version of [`take_decision`] for the cube of the current game of `score`.
+/// Takes if the winning chance is above the take point derived from the match equities,
+/// which allows free drops, e.g. for the leader at 1-away/2-away after the Crawford game.
+pub fn match_take_decision(board: &Board, score: &Match, rng: &mut impl Rng) -> CubeAction {
+    if score.cube() >= MAX_CUBE_VALUE {
+        return CubeAction::Pass;
+    }
+    let (away, opponent_away, crawford_done) = away_scores(board, score);
+    let take_point = match_take_point(opponent_away, away, score.cube() as i16, crawford_done);
+    if 1.0 - win_probability(board, CUBE_SIMULATIONS, rng) >= take_point {
+        CubeAction::Take
+    } else {
+        CubeAction::Pass
+    }
+}
+
+/// Points the player on roll and the opponent still need and whether the Crawford game
+/// is over once the current game has ended.
+fn away_scores(board: &Board, score: &Match) -> (i16, i16, bool) {
+    let (active, passive) = match board.get_active_player() {
+        Player::White => (crate::backgammon::Player::White, crate::backgammon::Player::Black),
+        Player::Black => (crate::backgammon::Player::Black, crate::backgammon::Player::White),
+    };
+    let away = |player| score.length() as i16 - score.score(player) as i16;
+    (away(active), away(passive), score.crawford_played() || score.is_crawford_game())
+}
+
+/// Minimum winning chance for a player `away` points from winning the match to take a
+/// double to `2 * cube` from an opponent `opponent_away` points away.
+fn match_take_point(away: i16, opponent_away: i16, cube: i16, crawford_done: bool) -> f32 {
+    let pass = match_equity(away, opponent_away - cube, crawford_done);
+    let win = match_equity(away - 2 * cube, opponent_away, crawford_done);
+    let lose = match_equity(away, opponent_away - 2 * cube, crawford_done);
+    if win <= lose {
+        return 0.0;
+    }
+    (pass - lose) / (win - lose)
+}
+
+/// Cubeless chance to win the match for a player `away` points from winning against an
+/// opponent `opponent_away` points away, assuming equal players and [`GAMMON_RATE`].
+/// After the Crawford game the trailer doubles immediately, so every game is worth twice as much.
+fn match_equity(away: i16, opponent_away: i16, crawford_done: bool) -> f32 {
+    fn equity(away: i16, opponent_away: i16, crawford_done: bool, memo: &mut HashMap<(i16, i16, bool), f32>) -> f32 {
+        if away <= 0 {
+            return 1.0;
+        }
+        if opponent_away <= 0 {
+            return 0.0;
+        }
+        if away == 1 && opponent_away == 1 {
+            return 0.5;
+        }
+        if away > 1 && opponent_away == 1 {
+            return 1.0 - equity(opponent_away, away, crawford_done, memo);
+        }
+        if let Some(&e) = memo.get(&(away, opponent_away, crawford_done)) {
+            return e;
+        }
+        // The game after a player reaches 1-away is the Crawford game, after it the trailer doubles.
+        let next_crawford_done = crawford_done || away == 1;
+        let lost = if away == 1 && crawford_done { 2 } else { 1 };
+        let e = 0.5 * (1.0 - GAMMON_RATE) * equity(away - 1, opponent_away, next_crawford_done, memo)
+            + 0.5 * GAMMON_RATE * equity(away - 2, opponent_away, next_crawford_done, memo)
+            + 0.5 * (1.0 - GAMMON_RATE) * equity(away, opponent_away - lost, next_crawford_done, memo)
+            + 0.5 * GAMMON_RATE * equity(away, opponent_away - 2 * lost, next_crawford_done, memo);
+        memo.insert((away, opponent_away, crawford_done), e);
+        e
+    }
+    equity(away, opponent_away, crawford_done, &mut HashMap::new())
+}
+
 // use rand::prelude::*;
 use std::f32::consts::SQRT_2;
 
@@ -173,6 +1051,26 @@ use std::f32::consts::SQRT_2;
 const EXPLORATION_CONSTANT: f32 = SQRT_2;
 const ROLLOUT_DEPTH: usize = 2;
 
+/// Parameters of [`mcts_search_with`]. The default uses 10000 iterations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    pub iterations: u32,
+    /// Number of plies played by a rollout before the position is evaluated.
+    pub rollout_depth: usize,
+    /// Weight of the exploration term when selecting a child.
+    pub exploration: f32,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            iterations: 10_000,
+            rollout_depth: ROLLOUT_DEPTH,
+            exploration: EXPLORATION_CONSTANT,
+        }
+    }
+}
+
 // Node in the MCTS: either a player-decision node or a chance (dice-roll) node.
 enum Node {
     Player(PlayerNode),
@@ -209,10 +1107,9 @@ impl PlayerNode {
         }
     }
 
-    fn traverse(&mut self, root_player: Player) -> f32 {
+    fn traverse(&mut self, root_player: Player, config: &MctsConfig, rng: &mut impl Rng) -> f32 {
         if self.board.outcome() != GameOutcome::Ongoing {
-            let val = self.board.eval();
-            return if self.board.active_player() == root_player { val } else { -val };
+            return rollout_reward(&self.board, root_player);
         }
 
         if let Some(mov) = self.untried_moves.pop() {
@@ -220,7 +1117,7 @@ impl PlayerNode {
             next_board.make_move_unchecked(mov);
             let mut child_node = Node::Chance(ChanceNode::new(next_board));
             let reward = match &mut child_node {
-                Node::Chance(cn) => cn.simulate(root_player),
+                Node::Chance(cn) => cn.simulate(root_player, config, rng),
                 _ => unreachable!(),
             };
             if let Node::Chance(cn) = child_node {
@@ -244,7 +1141,7 @@ impl PlayerNode {
                 Node::Chance(cn) => (cn.visits as f32, cn.total_value),
             };
             let exploitation = child_value / child_visits;
-            let exploration = EXPLORATION_CONSTANT * ((self.visits as f32).ln() / child_visits).sqrt();
+            let exploration = config.exploration * ((self.visits as f32).ln() / child_visits).sqrt();
             let score = exploitation + exploration;
             if score > best_score {
                 best_score = score;
@@ -253,8 +1150,8 @@ impl PlayerNode {
         }
 
         let reward = match &mut self.children[best_index].1 {
-            Node::Player(pn) => pn.traverse(root_player),
-            Node::Chance(cn) => cn.traverse(root_player),
+            Node::Player(pn) => pn.traverse(root_player, config, rng),
+            Node::Chance(cn) => cn.traverse(root_player, config, rng),
         };
         self.visits += 1;
         self.total_value += reward;
@@ -273,20 +1170,19 @@ impl ChanceNode {
         }
     }
 
-    fn simulate(&mut self, root_player: Player) -> f32 {
-        simulate_rollout(self.board, None, root_player)
+    fn simulate(&mut self, root_player: Player, config: &MctsConfig, rng: &mut impl Rng) -> f32 {
+        simulate_rollout(self.board, None, root_player, config.rollout_depth, rng)
     }
 
-    fn traverse(&mut self, root_player: Player) -> f32 {
+    fn traverse(&mut self, root_player: Player, config: &MctsConfig, rng: &mut impl Rng) -> f32 {
         if self.board.outcome() != GameOutcome::Ongoing {
-            let val = self.board.eval();
-            return if self.board.active_player() == root_player { val } else { -val };
+            return rollout_reward(&self.board, root_player);
         }
 
         if let Some(dice) = self.untried_rolls.pop() {
             let mut child_node = Node::Player(PlayerNode::new(self.board, dice));
             let reward = match &mut child_node {
-                Node::Player(pn) => simulate_rollout(pn.board, Some(pn.dice), root_player),
+                Node::Player(pn) => simulate_rollout(pn.board, Some(pn.dice), root_player, config.rollout_depth, rng),
                 _ => unreachable!(),
             };
             if let Node::Player(pn) = child_node {
@@ -302,8 +1198,7 @@ impl ChanceNode {
             return reward;
         }
 
-        let mut rng = thread_rng();
-        let r: f32 = nannou::rand::random();
+        let r: f32 = rng.random();
         let mut cum = 0.0;
         let mut chosen_index = 0;
         for (i, (dice, _)) in self.children.iter().enumerate() {
@@ -315,8 +1210,8 @@ impl ChanceNode {
         }
 
         let reward = match &mut self.children[chosen_index].1 {
-            Node::Player(pn) => pn.traverse(root_player),
-            Node::Chance(cn) => cn.traverse(root_player),
+            Node::Player(pn) => pn.traverse(root_player, config, rng),
+            Node::Chance(cn) => cn.traverse(root_player, config, rng),
         };
         self.visits += 1;
         self.total_value += reward;
@@ -330,15 +1225,18 @@ fn simulate_move_eval(board: Board, mv: Move) -> f32 {
     next.eval()
 }
 
-fn simulate_rollout(mut board: Board, mut opt_dice: Option<Dice>, root_player: Player) -> f32 {
-    let mut rng = thread_rng();
-    for _ in 0..ROLLOUT_DEPTH {
+fn simulate_rollout(mut board: Board, mut opt_dice: Option<Dice>, root_player: Player, depth: usize, rng: &mut impl Rng) -> f32 {
+    for _ in 0..depth {
         if board.outcome() != GameOutcome::Ongoing {
             break;
         }
+        // The trapped side cannot move until a point opens, the evaluation already accounts for the closeout.
+        if board.closed_out_checkers(Player::White) > 0 || board.closed_out_checkers(Player::Black) > 0 {
+            break;
+        }
 
         let dice = opt_dice.take().unwrap_or_else(|| {
-            let r: f32 = nannou::rand::random();
+            let r: f32 = rng.random();
             let mut cum = 0.0;
             for d in Dice::ALL {
                 cum += d.probability();
@@ -353,7 +1251,7 @@ fn simulate_rollout(mut board: Board, mut opt_dice: Option<Dice>, root_player: P
         if !moves.is_empty() {
             let len = moves.len();
     
-            let indx = (random::<f32>().pow(16) * (len as f32 - 1.0)) as usize; 
+            let indx = (rng.random::<f32>().powi(16) * (len as f32 - 1.0)) as usize; 
             // println!("Choosing move at index: {}\\{}", indx, len);
         
             let mut evals = moves.into_iter()
@@ -374,37 +1272,460 @@ fn simulate_rollout(mut board: Board, mut opt_dice: Option<Dice>, root_player: P
         }
     }
 
-    let val = board.eval();
-    if board.active_player() == root_player { val } else { -val }
+    rollout_reward(&board, root_player)
+}
+
+pub fn mcts_search(root_board: Board, dice: Dice, iterations: u32, rng: &mut impl Rng) -> Move {
+    mcts_search_with(root_board, dice, &MctsConfig { iterations, ..MctsConfig::default() }, rng)
+}
+
+/// Monte Carlo tree search with the iterations, rollout depth and exploration of `config`.
+pub fn mcts_search_with(root_board: Board, dice: Dice, config: &MctsConfig, rng: &mut impl Rng) -> Move {
+    most_visited(root_visits(root_board, dice, config, rng))
+}
+
+/// Root-parallel version of [`mcts_search`]. Splits `iterations` across `threads` independent
+/// trees searched in parallel and picks the move with the most visits summed over all trees.
+pub fn mcts_search_parallel(root_board: Board, dice: Dice, iterations: u32, threads: usize, rng: &mut impl Rng) -> Move {
+    assert!(threads > 0, "mcts_search_parallel needs at least one thread");
+    let seeds = (0..threads).map(|_| rng.random()).collect::<Vec<u64>>();
+    let threads = threads as u32;
+    let trees = (0..threads).into_par_iter()
+        .zip(seeds.into_par_iter())
+        .map(|(i, seed)| {
+            let config = MctsConfig {
+                iterations: iterations / threads + u32::from(i < iterations % threads),
+                ..MctsConfig::default()
+            };
+            root_visits(root_board, dice, &config, &mut StdRng::seed_from_u64(seed))
+        })
+        .collect::<Vec<_>>();
+
+    let mut merged: Vec<(Move, u32)> = Vec::new();
+    for (mv, visits) in trees.into_iter().flatten() {
+        match merged.iter_mut().find(|(m, _)| *m == mv) {
+            Some((_, total)) => *total += visits,
+            None => merged.push((mv, visits)),
+        }
+    }
+    most_visited(merged)
 }
 
-pub fn mcts_search(root_board: Board, dice: Dice, iterations: u32) -> Move {
+/// Searches a single tree and returns the visit count of every root move.
+/// Moves that were never expanded come first with zero visits.
+fn root_visits(root_board: Board, dice: Dice, config: &MctsConfig, rng: &mut impl Rng) -> Vec<(Move, u32)> {
     let root_player = root_board.active_player();
-    let mut root_node = Node::Player(PlayerNode::new(root_board, dice));
+    let mut root_node = PlayerNode::new(root_board, dice);
 
-    for _ in 0..iterations {
-        let _ = match &mut root_node {
-            Node::Player(pn) => pn.traverse(root_player),
-            Node::Chance(cn) => cn.traverse(root_player),
-        };
+    for _ in 0..config.iterations {
+        root_node.traverse(root_player, config, rng);
     }
 
-    if let Node::Player(pn) = root_node {
-        let mut best_move = pn.untried_moves.first().cloned()
-            .unwrap_or_else(|| pn.children[0].0);
-        let mut best_visits = 0;
-        for (mv, child) in pn.children {
+    let children = root_node.children.into_iter()
+        .map(|(mv, child)| {
             let visits = match child {
                 Node::Player(pn) => pn.visits,
                 Node::Chance(cn) => cn.visits,
             };
-            if visits > best_visits {
-                best_visits = visits;
-                best_move = mv;
+            (mv, visits)
+        });
+    root_node.untried_moves.into_iter()
+        .map(|mv| (mv, 0))
+        .chain(children)
+        .collect()
+}
+
+/// The first move with the highest visit count.
+fn most_visited(visits: Vec<(Move, u32)>) -> Move {
+    visits.into_iter()
+        .reduce(|best, candidate| if candidate.1 > best.1 { candidate } else { best })
+        .map(|(mv, _)| mv)
+        .expect("No moves available")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_scores_moves_by_eval() {
+        // Black to move from the starting position, which is not covered by the opening book.
+        let mut board = Board::new();
+        board.switch_player();
+        let dice = Dice::new(3, 1);
+        // Without lookahead every move is scored by the evaluation of the resulting position.
+        let evals = analyze(&board, dice, 0);
+        assert_eq!(evals.len(), board.generate_moves(dice).len());
+        assert!(evals.windows(2).all(|w| w[0].1 >= w[1].1));
+        for (m, eval) in evals {
+            let mut after = board;
+            after.make_move_unchecked(m);
+            assert_eq!(eval, -after.eval());
+        }
+    }
+
+    #[test]
+    fn test_difficulty_levels() {
+        // Black bears off its last checker next turn. White wins with 6/off 4/off,
+        // but 6/2 4/off loses.
+        let mut points = [0; 24];
+        points[18] = 1;
+        points[20] = 1;
+        points[0] = -1;
+        let board = Board::from_parts(points, (0, 0), (13, 14), Player::White);
+        let dice = Dice::new(6, 4);
+        let wins = |m: &Move| {
+            let mut new_board = board;
+            new_board.make_move_unchecked(*m);
+            new_board.outcome() != GameOutcome::Ongoing
+        };
+
+        for level in [Difficulty::Intermediate, Difficulty::Expert] {
+            for _ in 0..20 {
+                assert!(wins(&choose_move_with_difficulty(&board, dice, level)), "{:?}", level);
             }
         }
-        best_move
-    } else {
-        panic!("Root node must be a PlayerNode");
+        let beginner_losses = (0..200)
+            .filter(|_| !wins(&choose_move_with_difficulty(&board, dice, Difficulty::Beginner)))
+            .count();
+        assert!(beginner_losses > 0);
+    }
+
+    #[test]
+    fn test_play_match() {
+        let stats = play_match(&GreedyBot, &RandomBot::new(1), 20, 42);
+        assert_eq!(stats.games(), 20);
+        assert_eq!(stats.unfinished, 0);
+        assert!(stats.total_wins(Player::White) > stats.total_wins(Player::Black), "{:?}", stats);
+        assert!(stats.points(Player::White) >= stats.total_wins(Player::White));
+        assert_eq!(play_match(&GreedyBot, &RandomBot::new(1), 20, 42), stats);
+    }
+
+    #[test]
+    fn test_find_best_move_uses_opening_book() {
+        let board = Board::new();
+        let dice = Dice::new(3, 1);
+        let book = Move::from("8/5 6/5".parse::<crate::backgammon::Move>().unwrap());
+        let best = find_best_move(&board, dice, 1);
+        assert!(best.unordered_equal(&book));
+        assert!(board.generate_moves(dice).iter().any(|m| m.unordered_equal(&best)));
+    }
+
+    #[test]
+    fn test_pv_length_bounded_by_depth() {
+        let board = Board::new();
+        let dice = Dice::new(3, 1);
+        for depth in 0..=1 {
+            let (best_move, pv) = find_best_move_pv(&board, dice, depth);
+            assert_eq!(best_move, find_best_move(&board, dice, depth));
+            assert!(pv.len() <= depth as usize);
+            assert_eq!(pv_to_string(&pv).is_empty(), pv.is_empty());
+        }
+
+        // The variation replays legally from the position after the best move.
+        let (best_move, pv) = find_best_move_pv(&board, dice, 2);
+        assert!(!pv.is_empty());
+        let mut current = board;
+        current.make_move_unchecked(best_move);
+        for (dice, m) in pv {
+            assert!(current.generate_moves(dice).contains(&m));
+            current.make_move_unchecked(m);
+        }
+    }
+
+    #[test]
+    fn test_pv_to_string_uses_standard_notation() {
+        let m = Move::from("8/5 6/5".parse::<crate::backgammon::Move>().unwrap());
+        let bar = Move::from("bar/22".parse::<crate::backgammon::Move>().unwrap());
+        assert_eq!(pv_to_string(&[(Dice::new(3, 1), m), (Dice::new(3, 5), bar)]), "3/1: 8/5 6/5 | 3/5: bar/22");
+        assert_eq!(pv_to_string(&[]), "");
+    }
+
+    #[test]
+    fn test_transposition_table_for_depth() {
+        assert!(TranspositionTable::for_depth(0).capacity() < TranspositionTable::for_depth(1).capacity());
+        assert!(TranspositionTable::for_depth(1).capacity() < TranspositionTable::DEFAULT_CAPACITY);
+        assert_eq!(TranspositionTable::for_depth(u8::MAX).capacity(), TranspositionTable::DEFAULT_CAPACITY);
+    }
+
+    #[test]
+    fn test_analyze_with_reused_table() {
+        let mut board = Board::new();
+        board.switch_player();
+        let dice = Dice::new(6, 4);
+        let seen = TranspositionTable::for_depth(1);
+        let first = analyze_with_table(&board, dice, 1, &seen);
+        assert_eq!(first, analyze(&board, dice, 1));
+        let hits = seen.hits();
+        assert_eq!(analyze_with_table(&board, dice, 1, &seen), first);
+        assert!(seen.hits() > hits);
+    }
+
+    #[test]
+    fn test_transposition_table_serves_repeated_position() {
+        let board = Board::new();
+        let dice = Dice::new(3, 1);
+        let seen = TranspositionTable::with_capacity(1 << 12);
+        let first = alpha_beta(&board, 1, f32::NEG_INFINITY, f32::INFINITY, dice, Search::new(&seen));
+        assert_eq!(seen.hits(), 0);
+        let second = alpha_beta(&board, 1, f32::NEG_INFINITY, f32::INFINITY, dice, Search::new(&seen));
+        assert_eq!(seen.hits(), 1);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_move_ordering_reduces_nodes() {
+        let mut board = Board::new();
+        // Running a back checker leaves a blot in reach of the opponent's 5-3.
+        board.make_move_unchecked(board.generate_moves(Dice::new(5, 2))[0]);
+        let dice = Dice::new(5, 3);
+        let nodes = |move_ordering: bool| {
+            let seen = TranspositionTable::with_capacity(1 << 12);
+            let nodes = AtomicUsize::new(0);
+            let search = Search { nodes: Some(&nodes), move_ordering, ..Search::new(&seen) };
+            alpha_beta(&board, 2, f32::NEG_INFINITY, f32::INFINITY, dice, search);
+            nodes.into_inner()
+        };
+        let (ordered, unordered) = (nodes(true), nodes(false));
+        assert!(ordered < unordered);
+    }
+
+    #[test]
+    fn test_monte_carlo_search_is_deterministic_with_seed() {
+        let board = Board::new();
+        let dice = Dice::new(4, 2);
+        let search = |seed| monte_carlo_search(&board, dice, 20, 4, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(search(7), search(7));
+        assert!(board.generate_moves(dice).contains(&search(7)));
+    }
+
+    #[test]
+    fn test_mcts_search_parallel_returns_legal_move() {
+        let board = Board::new();
+        let dice = Dice::new(6, 1);
+        let mv = mcts_search_parallel(board, dice, 200, 4, &mut StdRng::seed_from_u64(0));
+        assert!(board.generate_moves(dice).contains(&mv));
+    }
+
+    #[test]
+    fn test_mcts_search_with_configs_returns_legal_moves() {
+        let board = Board::new();
+        let dice = Dice::new(4, 2);
+        let legal_moves = board.generate_moves(dice);
+        let mut rng = StdRng::seed_from_u64(0);
+        for exploration in [0.1, 5.0] {
+            let config = MctsConfig { iterations: 200, rollout_depth: 1, exploration };
+            assert!(legal_moves.contains(&mcts_search_with(board, dice, &config, &mut rng)));
+        }
+    }
+
+    #[test]
+    fn test_rollout_reward_grades_gammons() {
+        // White bears off the last checker with any roll, Black has borne off one checker or none.
+        let mut points = [0; 24];
+        points[23] = 1;
+        points[0] = -14;
+        let win = Board::from_parts(points, (0, 0), (14, 1), Player::White);
+        points[0] = -15;
+        let gammon = Board::from_parts(points, (0, 0), (14, 0), Player::White);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let win_reward = simulate_rollout(win, None, Player::White, 2, &mut rng);
+        let gammon_reward = simulate_rollout(gammon, None, Player::White, 2, &mut rng);
+        assert_eq!(win_reward, 1.0);
+        assert_eq!(gammon_reward, 2.0);
+        assert_eq!(simulate_random_game(&gammon, 2, &mut rng), 2.0);
+    }
+
+    #[test]
+    fn test_cube_clearly_pass() {
+        // White needs any roll to bear off the last checker, Black has borne off 13 checkers.
+        let mut points = [0; 24];
+        points[23] = 1;
+        points[5] = -2;
+        let board = Board::from_parts(points, (0, 0), (14, 13), Player::White);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(cube_decision(&board, 1, &mut rng), CubeAction::Double);
+        assert_eq!(take_decision(&board, 1, &mut rng), CubeAction::Pass);
+    }
+
+    #[test]
+    fn test_cube_too_good_to_double() {
+        // White bears off the last checker with any roll and wins a gammon, Black has not
+        // started bearing off. Doubling would turn the gammon into a single point.
+        let mut points = [0; 24];
+        points[23] = 1;
+        points[5] = -15;
+        let board = Board::from_parts(points, (0, 0), (14, 0), Player::White);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(game_probabilities(&board, CUBE_SIMULATIONS, &mut rng).gammon, 1.0);
+        assert_eq!(cube_decision(&board, 1, &mut rng), CubeAction::NoDouble);
+        assert_eq!(take_decision(&board, 1, &mut rng), CubeAction::Pass);
+    }
+
+    #[test]
+    fn test_match_cube_decision_two_away_two_away() {
+        // White wins about 57% of the time, inside the money doubling window only at 2-away/2-away.
+        let mut points = [0; 24];
+        points[18] = 1;
+        points[20] = 1;
+        points[2] = -2;
+        let board = Board::from_parts(points, (0, 0), (13, 13), Player::White);
+        let mut rng = StdRng::seed_from_u64(0);
+        let p = win_probability(&board, CUBE_SIMULATIONS, &mut rng);
+        assert!((p - 0.57).abs() < 0.04, "estimated {p}");
+        let mut score = Match::new(5);
+        score.record_game(crate::backgammon::GameOutcome::Backgammon(crate::backgammon::Player::White), 1);
+        score.record_game(crate::backgammon::GameOutcome::Backgammon(crate::backgammon::Player::Black), 1);
+
+        assert_eq!(cube_decision(&board, 1, &mut rng), CubeAction::NoDouble);
+        assert_eq!(match_cube_decision(&board, &score, &mut rng), CubeAction::Double);
+    }
+
+    #[test]
+    fn test_match_cube_decision_post_crawford() {
+        // White wins about 61% of the time as the trailer at 2-away against 1-away.
+        let mut points = [0; 24];
+        points[18] = 1;
+        points[20] = 1;
+        points[4] = -1;
+        points[1] = -1;
+        let board = Board::from_parts(points, (0, 0), (13, 13), Player::White);
+        let mut rng = StdRng::seed_from_u64(0);
+        let p = win_probability(&board, CUBE_SIMULATIONS, &mut rng);
+        assert!((p - 0.61).abs() < 0.04, "estimated {p}");
+        let mut score = Match::new(3);
+        score.record_game(crate::backgammon::GameOutcome::Gammon(crate::backgammon::Player::Black), 1);
+        score.record_game(crate::backgammon::GameOutcome::Win(crate::backgammon::Player::White), 1);
+        assert!(score.crawford_played());
+
+        assert_eq!(cube_decision(&board, 1, &mut rng), CubeAction::NoDouble);
+        assert_eq!(match_cube_decision(&board, &score, &mut rng), CubeAction::Double);
+        // Black can drop for free, 2-away/1-away is as good as 1-away/1-away.
+        assert_eq!(take_decision(&board, 1, &mut rng), CubeAction::Take);
+        assert_eq!(match_take_decision(&board, &score, &mut rng), CubeAction::Pass);
+    }
+
+    #[test]
+    fn test_cube_clearly_take() {
+        // White has two checkers on the 6-point, Black two on the 4-point.
+        let mut points = [0; 24];
+        points[18] = 2;
+        points[3] = -2;
+        let board = Board::from_parts(points, (0, 0), (13, 13), Player::White);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(cube_decision(&board, 1, &mut rng), CubeAction::NoDouble);
+        assert_eq!(take_decision(&board, 1, &mut rng), CubeAction::Take);
+    }
+
+    #[test]
+    fn test_monte_carlo_probabilities_bounds() {
+        let board = Board::new();
+        let dice = Dice::new(3, 1);
+        let probabilities = monte_carlo_probabilities(&board, dice, 5, 4, &mut StdRng::seed_from_u64(0));
+        assert_eq!(probabilities.len(), board.generate_moves(dice).len());
+        for (_, p) in probabilities {
+            for value in [p.win, p.gammon, p.backgammon] {
+                assert!((0.0..=1.0).contains(&value));
+            }
+            assert!(p.gammon <= p.win);
+            assert!(p.backgammon <= p.gammon);
+        }
+    }
+
+    #[test]
+    fn test_expectiminimax_depth_one() {
+        // White has a single checker on the 19-point, Black a single checker on its 2-point.
+        // With 1-2 every move ends on the 16-point, which Black values at 19 - 9 = 10.
+        let mut points = [0; 24];
+        points[5] = 1;
+        points[1] = -1;
+        let board = Board::from_parts(points, (0, 0), (14, 14), Player::White);
+        assert_eq!(expectiminimax(&board, Dice::new(1, 2), 1), -10.0);
+        assert_eq!(expectiminimax(&board, Dice::new(1, 2), 0), board.eval());
+    }
+
+    #[test]
+    fn test_expectiminimax_terminal() {
+        // Any roll bears off the last checker for a single win.
+        let mut points = [0; 24];
+        points[23] = 1;
+        points[2] = -1;
+        let board = Board::from_parts(points, (0, 0), (14, 14), Player::White);
+        assert_eq!(expectiminimax(&board, Dice::new(6, 5), 1), 1000.0);
+        assert_eq!(expectiminimax(&board, Dice::new(6, 5), 2), 1000.0);
+    }
+
+    #[test]
+    fn test_best_move_expectimax_matches_brute_force() {
+        // Two White checkers against a single Black checker, every roll of Black is a decision.
+        let mut points = [0; 24];
+        points[8] = 1;
+        points[4] = 1;
+        points[14] = -1;
+        let board = Board::from_parts(points, (0, 0), (13, 14), Player::White);
+        let dice = Dice::new(3, 1);
+
+        let after = |board: &Board, m: Move| {
+            let mut board = *board;
+            board.make_move_unchecked(m);
+            board
+        };
+        let expected_reply = |board: &Board| -> f32 {
+            let (sum, total) = Dice::ALL.iter().fold((0.0, 0.0), |(sum, total), &roll| {
+                let best = board.generate_moves(roll).into_iter()
+                    .map(|reply| -after(board, reply).eval())
+                    .fold(f32::NEG_INFINITY, f32::max);
+                (sum + best * roll.probability(), total + roll.probability())
+            });
+            sum / total
+        };
+        let values = board.generate_moves(dice).into_iter()
+            .map(|m| (m, -expected_reply(&after(&board, m))))
+            .collect::<Vec<_>>();
+        assert!(values.len() > 1);
+        let best_value = values.iter().map(|&(_, value)| value).fold(f32::NEG_INFINITY, f32::max);
+
+        let chosen = best_move_expectimax(&board, dice, 1);
+        let chosen_value = values.iter().find(|&&(m, _)| m == chosen).unwrap().1;
+        assert!((chosen_value - best_value).abs() < 1e-3);
+        assert!((-expected_reply(&after(&board, chosen)) - expectiminimax(&board, dice, 2)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_move_cache_matches_uncached_search() {
+        let mut board = Board::new();
+        board.make_move_unchecked(find_best_move(&board, Dice::new(3, 1), 0));
+        let cache = MoveCache::new();
+        for dice in [Dice::new(6, 4), Dice::new(2, 2), Dice::new(5, 1)] {
+            let uncached = find_best_move(&board, dice, 1);
+            assert_eq!(find_best_move_cached(&board, dice, 1, &cache), uncached);
+            // The second search takes all move lists from the cache.
+            let hits = cache.hits();
+            assert_eq!(find_best_move_cached(&board, dice, 1, &cache), uncached);
+            assert!(cache.hits() > hits);
+            let mut ordered = board.generate_moves(dice);
+            order_moves(&board, &mut ordered);
+            assert_eq!(*cache.generate_moves(&board, dice), *ordered);
+        }
+        assert!(cache.len() <= cache.capacity());
+    }
+
+    #[test]
+    fn test_move_cache_evicts_least_recently_used() {
+        let cache = MoveCache::with_capacity(2);
+        let board = Board::new();
+        let (a, b, c) = (Dice::new(3, 1), Dice::new(6, 5), Dice::new(4, 4));
+        cache.generate_moves(&board, a);
+        cache.generate_moves(&board, b);
+        cache.generate_moves(&board, a);
+        assert_eq!(cache.hits(), 1);
+        // `b` is the least recently used entry and makes room for `c`.
+        cache.generate_moves(&board, c);
+        assert_eq!(cache.len(), 2);
+        cache.generate_moves(&board, a);
+        assert_eq!(cache.hits(), 2);
+        cache.generate_moves(&board, b);
+        assert_eq!(cache.hits(), 2);
     }
 }
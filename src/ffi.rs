@@ -0,0 +1,176 @@
+//! C API for embedding the engine, enabled with the `cdylib` feature.
+//! The shared library is built with `cargo rustc --lib --release --features cdylib --crate-type cdylib`.
+//!
+//! Ownership: a board returned by [`bg_board_new`] is owned by the caller and must be
+//! released with exactly one call to [`bg_board_free`]. All other functions only borrow it.
+//!
+//! Null handling: every function accepts null pointers and reports them with
+//! [`BG_ERR_NULL`] instead of dereferencing them, [`bg_board_free`] ignores null.
+//!
+//! Moves are exchanged as NUL-terminated UTF-8 strings in standard notation, see [`Move`].
+
+use std::ffi::{CStr, c_char};
+
+use crate::backgammon::{Board, Dice, Move};
+use crate::engine;
+
+/// The call succeeded.
+pub const BG_OK: i32 = 0;
+/// A pointer argument was null.
+pub const BG_ERR_NULL: i32 = -1;
+/// A die is not between 1 and 6.
+pub const BG_ERR_INVALID_DICE: i32 = -2;
+/// The notation is not valid UTF-8 or not a move in standard notation.
+pub const BG_ERR_PARSE: i32 = -3;
+/// The move cannot be played in the position.
+pub const BG_ERR_ILLEGAL_MOVE: i32 = -4;
+/// The output buffer cannot hold the notation and its terminating NUL.
+pub const BG_ERR_BUFFER_TOO_SMALL: i32 = -5;
+
+/// Allocates the starting position with White to move. Free it with [`bg_board_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn bg_board_new() -> *mut Board {
+    Box::into_raw(Box::new(Board::new()))
+}
+
+/// Releases a board allocated by [`bg_board_new`]. Does nothing for null.
+///
+/// # Safety
+/// `board` must be null or a pointer returned by [`bg_board_new`] that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bg_board_free(board: *mut Board) {
+    if !board.is_null() {
+        drop(unsafe { Box::from_raw(board) });
+    }
+}
+
+/// Writes the best move for the roll, found by searching `depth` plies, as a NUL-terminated
+/// string into `out_notation_buf`. Returns [`BG_OK`] or a negative error code, the buffer
+/// is only written on success. A roll without a legal move yields an empty string.
+///
+/// # Safety
+/// `board` must be null or a live board from [`bg_board_new`], and `out_notation_buf` must be
+/// null or valid for writes of `buf_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bg_best_move(board: *const Board, die1: u8, die2: u8, depth: u8, out_notation_buf: *mut c_char, buf_len: usize) -> i32 {
+    let Some(board) = (unsafe { board.as_ref() }) else {
+        return BG_ERR_NULL;
+    };
+    if out_notation_buf.is_null() {
+        return BG_ERR_NULL;
+    }
+    if !(1..=6).contains(&die1) || !(1..=6).contains(&die2) {
+        return BG_ERR_INVALID_DICE;
+    }
+
+    let best = engine::find_best_legal_move(board, Dice::from_numbers(die1, die2), depth);
+    let notation = best.to_notation(board);
+    if notation.len() >= buf_len {
+        return BG_ERR_BUFFER_TOO_SMALL;
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(notation.as_ptr(), out_notation_buf.cast::<u8>(), notation.len());
+        *out_notation_buf.add(notation.len()) = 0;
+    }
+    BG_OK
+}
+
+/// Plays the move given in standard notation and passes the turn to the opponent.
+/// The call has no dice, so the move is accepted if it is legal for any roll.
+/// Returns [`BG_OK`] or a negative error code, the board is unchanged on error.
+///
+/// # Safety
+/// `board` must be null or a live board from [`bg_board_new`], and `notation` must be null
+/// or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bg_apply_move(board: *mut Board, notation: *const c_char) -> i32 {
+    let Some(board) = (unsafe { board.as_mut() }) else {
+        return BG_ERR_NULL;
+    };
+    if notation.is_null() {
+        return BG_ERR_NULL;
+    }
+    let Some(m) = unsafe { CStr::from_ptr(notation) }.to_str().ok().and_then(|notation| notation.parse::<Move>().ok()) else {
+        return BG_ERR_PARSE;
+    };
+
+    if Dice::ALL.iter().any(|&dice| board.make_move(m, dice).is_ok()) {
+        BG_OK
+    } else {
+        BG_ERR_ILLEGAL_MOVE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use crate::backgammon::{GameOutcome, Player};
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let board = bg_board_new();
+        let mut buf = [0 as c_char; 64];
+        unsafe {
+            assert_eq!(bg_best_move(board, 3, 1, 1, buf.as_mut_ptr(), buf.len()), BG_OK);
+            let notation = CStr::from_ptr(buf.as_ptr()).to_owned();
+            assert_eq!(notation.to_str().unwrap(), "8/5 6/5");
+
+            assert_eq!(bg_apply_move(board, notation.as_ptr()), BG_OK);
+            let mut expected = Board::new();
+            expected.make_move("8/5 6/5".parse().unwrap(), Dice::from_numbers(3, 1)).unwrap();
+            assert_eq!(*board, expected);
+            assert_eq!((*board).active_player(), Player::Black);
+            bg_board_free(board);
+        }
+    }
+
+    #[test]
+    fn test_best_move_can_be_applied_in_random_games() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut buf = [0 as c_char; 64];
+        for _ in 0..20 {
+            let board = bg_board_new();
+            unsafe {
+                while (*board).outcome() == GameOutcome::Ongoing {
+                    let dice = Dice::roll_with(&mut rng);
+                    assert_eq!(bg_best_move(board, dice.die1(), dice.die2(), 0, buf.as_mut_ptr(), buf.len()), BG_OK);
+                    let notation = CStr::from_ptr(buf.as_ptr()).to_str().unwrap();
+                    let mut expected = *board;
+                    assert_eq!(expected.make_move(notation.parse().unwrap(), dice), Ok(()), "{} with {}", notation, dice);
+
+                    // Continue with a random move to reach positions the engine would avoid.
+                    let m = *(*board).generate_moves(dice).choose(&mut rng).unwrap();
+                    let notation = CString::new(m.to_notation(&*board)).unwrap();
+                    assert_eq!(bg_apply_move(board, notation.as_ptr()), BG_OK);
+                }
+                bg_board_free(board);
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let board = bg_board_new();
+        let mut buf = [0 as c_char; 4];
+        let illegal = CString::new("24/10").unwrap();
+        let malformed = CString::new("24-18").unwrap();
+        unsafe {
+            assert_eq!(bg_best_move(std::ptr::null(), 3, 1, 1, buf.as_mut_ptr(), buf.len()), BG_ERR_NULL);
+            assert_eq!(bg_best_move(board, 3, 1, 1, std::ptr::null_mut(), 0), BG_ERR_NULL);
+            assert_eq!(bg_best_move(board, 7, 1, 1, buf.as_mut_ptr(), buf.len()), BG_ERR_INVALID_DICE);
+            assert_eq!(bg_best_move(board, 3, 1, 1, buf.as_mut_ptr(), buf.len()), BG_ERR_BUFFER_TOO_SMALL);
+            assert_eq!(bg_apply_move(std::ptr::null_mut(), illegal.as_ptr()), BG_ERR_NULL);
+            assert_eq!(bg_apply_move(board, std::ptr::null()), BG_ERR_NULL);
+            assert_eq!(bg_apply_move(board, malformed.as_ptr()), BG_ERR_PARSE);
+            assert_eq!(bg_apply_move(board, illegal.as_ptr()), BG_ERR_ILLEGAL_MOVE);
+            assert_eq!(*board, Board::new());
+            bg_board_free(board);
+            bg_board_free(std::ptr::null_mut());
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use std::{mem::swap, num::NonZeroU8};
+use std::{cell::RefCell, mem::swap, num::NonZeroU8};
 
-use nannou::event::ElementState;
-use rand::random_range;
+use hashbrown::HashSet;
+use rand::{random_range, Rng};
+
+use crate::backgammon::zobrist::ZOBRIST_KEYS;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Player {
@@ -18,6 +20,24 @@ impl Player {
     }
 }
 
+impl From<crate::backgammon::Player> for Player {
+    fn from(player: crate::backgammon::Player) -> Self {
+        match player {
+            crate::backgammon::Player::White => Player::White,
+            crate::backgammon::Player::Black => Player::Black,
+        }
+    }
+}
+
+impl From<Player> for crate::backgammon::Player {
+    fn from(player: Player) -> Self {
+        match player {
+            Player::White => crate::backgammon::Player::White,
+            Player::Black => crate::backgammon::Player::Black,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GameOutcome {
     Win(Player),
@@ -26,6 +46,71 @@ pub enum GameOutcome {
     Ongoing,
 }
 
+impl GameOutcome {
+    /// Player who won the game, `None` while it is ongoing.
+    pub fn winner(&self) -> Option<Player> {
+        match *self {
+            GameOutcome::Win(player) | GameOutcome::Gammon(player) | GameOutcome::Backgammon(player) => Some(player),
+            GameOutcome::Ongoing => None,
+        }
+    }
+
+    /// Points won without the doubling cube: 1 for a win, 2 for a gammon and 3 for a backgammon.
+    pub fn base_points(&self) -> u8 {
+        match self {
+            GameOutcome::Win(_) => 1,
+            GameOutcome::Gammon(_) => 2,
+            GameOutcome::Backgammon(_) => 3,
+            GameOutcome::Ongoing => 0,
+        }
+    }
+
+    /// Points won with the doubling cube at `cube`.
+    pub fn points_with_cube(&self, cube: u8) -> u8 {
+        self.base_points() * cube
+    }
+}
+
+/// Weights used by [`Board::eval_with`]. The default matches the hand tuned values of [`Board::eval`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalParams {
+    /// Upper bound for the per checker progress multiplier.
+    pub pip_cap: i16,
+    /// Lower bound for the per checker progress multiplier.
+    pub pip_floor: i16,
+    /// Value of each borne off checker.
+    pub home_weight: i16,
+    /// Penalty for each checker on the bar.
+    pub bar_penalty: i16,
+    /// Bonus for each made point in either home board.
+    pub anchor_bonus: i16,
+    /// Penalty for each checker on the bar while the opponent holds all six home points,
+    /// see [`Board::closed_out_checkers`].
+    pub closeout_penalty: i16,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        EvalParams {
+            pip_cap: 19,
+            pip_floor: 6,
+            home_weight: 35,
+            bar_penalty: 0,
+            anchor_bonus: 8,
+            closeout_penalty: 60,
+        }
+    }
+}
+
+/// Raw evaluation that [`Board::eval_normalized`] maps to `tanh(1) ≈ 0.76`.
+const EVAL_NORMALIZATION_SCALE: f32 = 200.0;
+
+/// Weight of each pip of [`Board::captured_value`] in [`Board::quick_score`]. Hitting a checker
+/// that has travelled 20 pips is worth about as much as a 0.2 swing of `eval_normalized`.
+pub const QUICK_SCORE_CAPTURE_WEIGHT: f32 = 0.01;
+/// Weight of each pip of expected loss to hits in [`Board::quick_score`].
+pub const QUICK_SCORE_EXPOSURE_WEIGHT: f32 = 0.01;
+
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct Board {
     board: [i8; 24],
@@ -76,6 +161,32 @@ impl Board {
         self.active_player
     }
 
+    /// Zobrist hash of the board, see [`crate::backgammon::Board::zobrist_hash`].
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (index, &checkers) in self.board.iter().enumerate() {
+            if checkers != 0 {
+                let sign = (checkers < 0) as usize;
+                hash ^= ZOBRIST_KEYS.points[index][sign][checkers.unsigned_abs() as usize];
+            }
+        }
+        hash ^= ZOBRIST_KEYS.bars[0][self.active_bar as usize];
+        hash ^= ZOBRIST_KEYS.bars[1][self.inactive_bar as usize];
+        hash ^= ZOBRIST_KEYS.homes[0][self.active_home as usize];
+        hash ^= ZOBRIST_KEYS.homes[1][self.inactive_home as usize];
+        if self.active_player == Player::Black {
+            hash ^= ZOBRIST_KEYS.black_to_move;
+        }
+        hash
+    }
+
+    /// Key of everything [`Self::generate_moves`] depends on: the position, as its
+    /// [`crate::backgammon::Board::canonical_key`], and the roll. Valid positions with the
+    /// same fingerprint have the same legal moves.
+    pub fn legal_move_fingerprint(&self, dice: Dice) -> (u128, Dice) {
+        (crate::backgammon::Board::from(*self).canonical_key(), dice)
+    }
+
     pub fn to_fancy_string(&self) -> String {
         let board = self.from_whites_perspective();
         format!(
@@ -115,6 +226,20 @@ impl Board {
     }
 
     pub fn eval(&self) -> f32 {
+        self.eval_with(&EvalParams::default())
+    }
+
+    /// [`Self::eval`] scaled to a pseudo-equity. Ongoing positions are mapped into `(-1, 1)`,
+    /// finished games score ±1 for a win, ±2 for a gammon and ±3 for a backgammon.
+    pub fn eval_normalized(&self) -> f32 {
+        let raw = self.eval();
+        match self.outcome() {
+            GameOutcome::Ongoing => (raw / EVAL_NORMALIZATION_SCALE).tanh(),
+            _ => raw / 1000.0,
+        }
+    }
+
+    pub fn eval_with(&self, params: &EvalParams) -> f32 {
         match self.outcome() {
             GameOutcome::Win(player) if player == self.active_player => return 1000.0,
             GameOutcome::Win(_) => return -1000.0,
@@ -130,21 +255,25 @@ impl Board {
         for (i, &checker) in self.board.iter().enumerate() {
             if checker > 0 {
                 let mult = i as i16 + 1;
-                score += checker as i16 * mult.min(19).max(6);
+                score += checker as i16 * mult.min(params.pip_cap).max(params.pip_floor);
             } else if checker < 0 {
                 let mult = 24 - i as i16;
-                score += checker as i16 * mult.min(19).max(6);         
+                score += checker as i16 * mult.min(params.pip_cap).max(params.pip_floor);         
             } 
             if (i >= 18 || i < 6) && checker.abs() >= 2 {
                 if checker > 0 {
-                    score += 8;
+                    score += params.anchor_bonus;
                 } else {
-                    score -= 8;
+                    score -= params.anchor_bonus;
                 }
             } 
         }
         
-        score += (self.active_home as i16 - self.inactive_home as i16) * 35;
+        score += (self.active_home as i16 - self.inactive_home as i16) * params.home_weight;
+        score -= (self.active_bar as i16 - self.inactive_bar as i16) * params.bar_penalty;
+        // A closed out player cannot move until a point opens, which the pip count misses.
+        score -= (self.closed_out_checkers(self.active_player) as i16
+            - self.closed_out_checkers(self.active_player.opposite()) as i16) * params.closeout_penalty;
 
         score as f32
     }
@@ -160,6 +289,20 @@ impl Board {
         }
     }
 
+    /// Creates a board from raw parts, from the perspective of `active_player`.
+    /// Bars and homes are given as `(active, inactive)`.
+    #[cfg(test)]
+    pub(crate) fn from_parts(board: [i8; 24], bars: (u8, u8), homes: (u8, u8), active_player: Player) -> Self {
+        Board {
+            board,
+            active_bar: bars.0,
+            inactive_bar: bars.1,
+            active_home: homes.0,
+            inactive_home: homes.1,
+            active_player,
+        }
+    }
+
     pub fn bench() -> Self {
         Board {
             board: [1,-2,-2,1,1,0,1,-1,0,0,0,-2,-1,-1,0,0,0,0,6,-1,-1,-4,2,2],
@@ -184,6 +327,33 @@ impl Board {
         sum
     }
 
+    /// Cheap score of `m` for the active player without searching, higher is better:
+    /// `eval_normalized` of the resulting position, plus [`QUICK_SCORE_CAPTURE_WEIGHT`] per pip
+    /// of captured value, minus [`QUICK_SCORE_EXPOSURE_WEIGHT`] per pip of expected loss to hits
+    /// in the opponent's reply (see [`crate::backgammon::Board::total_blot_exposure`]).
+    /// `dice` is the roll `m` is played with. `m` is assumed to be legal for it, checking this
+    /// would generate all moves for every scored move.
+    pub fn quick_score(&self, m: &Move, _dice: Dice) -> f32 {
+        let mut board = *self;
+        board.make_move_unchecked(*m);
+        let equity = -board.eval_normalized();
+
+        let mut own_view = crate::backgammon::Board::from(board);
+        own_view.switch_player();
+        equity + QUICK_SCORE_CAPTURE_WEIGHT * self.captured_value(m) as f32
+            - QUICK_SCORE_EXPOSURE_WEIGHT * own_view.total_blot_exposure()
+    }
+
+    /// Move with the highest [`Self::quick_score`], a zero-search opponent that is stronger than
+    /// playing randomly and much faster than [`crate::engine::find_best_move`].
+    pub fn greedy_move(&self, dice: Dice) -> Move {
+        self.generate_moves(dice).into_iter()
+            .map(|m| (self.quick_score(&m, dice), m))
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, m)| m)
+            .unwrap_or(Move::new())
+    }
+
     pub fn switch_player(&mut self) {
         self.active_player = match self.active_player {
             Player::White => Player::Black,
@@ -218,10 +388,85 @@ impl Board {
         unsafe { &*(self.board[18..24].as_ptr() as *const [i8; 6]) }
     }
 
+    /// Total number of pips `player` needs to bear off all checkers.
+    pub fn pip_count(&self, player: Player) -> u16 {
+        let (sign, bar) = if player == self.active_player { (1, self.active_bar) } else { (-1, self.inactive_bar) };
+        let board_pips = self.board.iter()
+            .enumerate()
+            .filter(|&(_, &checkers)| checkers * sign > 0)
+            .map(|(i, &checkers)| {
+                let distance = if sign > 0 { 24 - i as u16 } else { i as u16 + 1 };
+                checkers.unsigned_abs() as u16 * distance
+            })
+            .sum::<u16>();
+        board_pips + bar as u16 * 25
+    }
+
+    /// Pip count of the active player minus the pip count of the passive player. Fewer pips
+    /// are better, so a negative difference means the active player leads the race and a
+    /// positive one that they trail it.
+    pub fn pip_difference(&self) -> i16 {
+        self.pip_count(self.active_player) as i16 - self.pip_count(self.active_player.opposite()) as i16
+    }
+
+    /// Number of checkers `player` has on the bar while the opponent holds all six points of
+    /// their home board, 0 if `player` is not closed out. These checkers cannot enter until a point opens.
+    pub fn closed_out_checkers(&self, player: Player) -> u8 {
+        if player == self.active_player {
+            if self.inactive_home_board().iter().all(|&c| c <= -2) { self.active_bar } else { 0 }
+        } else if self.active_home_board().iter().all(|&c| c >= 2) {
+            self.inactive_bar
+        } else {
+            0
+        }
+    }
+
+    /// Returns true if no more contact is possible, see [`crate::backgammon::Board::is_race`].
+    pub fn is_race(&self) -> bool {
+        if self.active_bar > 0 || self.inactive_bar > 0 {
+            return false;
+        }
+        let rearmost_active = self.board.iter().position(|&c| c > 0);
+        let rearmost_passive = self.board.iter().rposition(|&c| c < 0);
+        match (rearmost_active, rearmost_passive) {
+            (Some(active), Some(passive)) => active > passive,
+            _ => true,
+        }
+    }
+
+    /// Cheap estimate of the active player's chance to win a race from the pip counts.
+    /// The race length difference is modelled as normally distributed with a variance
+    /// proportional to the total pip count, being on roll is worth 4 pips.
+    /// Only meaningful if [`Self::is_race`] holds, see [`Self::checked_race_win_probability`].
+    pub fn race_win_probability(&self) -> f32 {
+        let own = self.pip_count(self.active_player) as f32;
+        let opponent = self.pip_count(self.active_player.opposite()) as f32;
+        let lead = opponent - own + 4.0;
+        // A roll moves 8.17 pips on average with a variance of 19.0.
+        let sigma = ((own + opponent) * 19.0 / 8.17).sqrt().max(1.0);
+        // Logistic approximation of the normal distribution function.
+        1.0 / (1.0 + (-1.702 * lead / sigma).exp())
+    }
+
+    /// [`Self::race_win_probability`] if the position is a race, `None` while contact remains.
+    pub fn checked_race_win_probability(&self) -> Option<f32> {
+        self.is_race().then(|| self.race_win_probability())
+    }
+
     pub fn can_bear_off(&self) -> bool {
         self.active_bar == 0 && self.active_home_board().iter().filter(|&&a| a > 0).sum::<i8>() + self.active_home as i8 == 15
     }
 
+    /// Returns true if neither the active player nor, after the forced pass, the opponent can
+    /// move with `dice`. For a single roll this happens in legal games, e.g. when both players
+    /// are on the bar and the entry points are blocked. Being stuck for every roll cannot happen
+    /// in a legal game and indicates a corrupt position.
+    pub fn is_stuck(&self, dice: Dice) -> bool {
+        let mut passed = *self;
+        passed.switch_player();
+        self.generate_half_moves(dice).is_empty() && passed.generate_half_moves(dice).is_empty()
+    }
+
     // Moves a checker from one position to another.
     // Fast but illegal moves can lead to undefined behavior.
     // Only use this function if you are sure the move is valid.
@@ -250,48 +495,10 @@ impl Board {
         self.switch_player();
     }
 
+    /// Generates all legal moves using scratch buffers kept per thread.
+    /// Use a [`MoveGenerator`] directly to avoid allocating the result as well.
     pub fn generate_moves(&self, dice: Dice) -> Vec<Move> {
-        let mut stack: Vec<(Dice, Board, Move)> = vec![(dice, *self, Move::new())];
-        let mut next_stack: Vec<(Dice, Board, Move)> = Vec::new();
-        
-        let mut best_result_len = 0;
-        let mut results = Vec::new();
-
-        loop {
-            while let Some((dice, board, previous_moves)) = stack.pop() {
-                let previous_moves_len = previous_moves.len();
-                if previous_moves_len > best_result_len {
-                    results.clear();
-                    best_result_len = previous_moves.len();
-                    results.push(previous_moves);
-                } else if previous_moves_len == best_result_len {
-                    results.push(previous_moves);
-                } 
-                if dice.is_used() {
-                    continue;
-                }
-                
-                let half_moves = board.generate_half_moves(dice);
-                
-                for &(hv, remaining_dice) in half_moves.iter() {
-                    let mut board = board.clone();
-                    board.make_half_move_unchecked(&hv);
-                    let mut mv = previous_moves;
-                    mv.append(hv);
-                    next_stack.push((remaining_dice, board, mv));
-                }
-            }
-            if next_stack.is_empty() {
-                break;
-            }
-            stack.clear();
-            for e in next_stack.drain(..) {
-                if !stack.iter().any(|(_, _, mv)| mv.unordered_equal(&e.2)) {
-                    stack.push(e);
-                }
-            }
-        }
-        results
+        MOVE_GENERATOR.with_borrow_mut(|generator| generator.generate_moves(self, dice).to_vec())
     }
 
     pub fn generate_half_moves(&self, dice: Dice) -> TinyVector<(HalfMoveEnum, Dice), 30> {
@@ -361,6 +568,144 @@ impl Board {
 
 }
 
+thread_local! {
+    static MOVE_GENERATOR: RefCell<MoveGenerator> = RefCell::new(MoveGenerator::new());
+}
+
+/// Move generation with scratch buffers that are reused across calls, so generating
+/// moves in a search loop does not allocate once the buffers have grown large enough.
+#[derive(Debug, Default)]
+pub struct MoveGenerator {
+    stack: Vec<(Dice, Board, Move)>,
+    next_stack: Vec<(Dice, Board, Move)>,
+    seen: HashSet<u64>,
+    results: Vec<Move>,
+}
+
+impl MoveGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates all legal moves for `board`. The result is valid until the next call.
+    pub fn generate_moves(&mut self, board: &Board, dice: Dice) -> &[Move] {
+        let MoveGenerator { stack, next_stack, seen, results } = self;
+        stack.clear();
+        next_stack.clear();
+        results.clear();
+        stack.push((dice, *board, Move::new()));
+
+        let mut best_result_len = 0;
+
+        loop {
+            while let Some((dice, board, previous_moves)) = stack.pop() {
+                let previous_moves_len = previous_moves.len();
+                if previous_moves_len > best_result_len {
+                    results.clear();
+                    best_result_len = previous_moves.len();
+                    results.push(previous_moves);
+                } else if previous_moves_len == best_result_len {
+                    results.push(previous_moves);
+                } 
+                if dice.is_used() {
+                    continue;
+                }
+                
+                let half_moves = board.generate_half_moves(dice);
+                
+                for &(hv, remaining_dice) in half_moves.iter() {
+                    let mut board = board;
+                    board.make_half_move_unchecked(&hv);
+                    let mut mv = previous_moves;
+                    mv.append(hv);
+                    next_stack.push((remaining_dice, board, mv));
+                }
+            }
+            if next_stack.is_empty() {
+                break;
+            }
+            stack.clear();
+            seen.clear();
+            for e in next_stack.drain(..) {
+                if seen.insert(e.2.unordered_key()) {
+                    stack.push(e);
+                }
+            }
+        }
+        // If only one die can be played, it has to be the larger one when possible.
+        if best_result_len == 1 && let Dice::Single { value_1, value_2, .. } = dice {
+            let larger = board.generate_half_moves(dice.use_die(value_1.min(value_2)));
+            if !larger.is_empty() {
+                results.retain(|m| larger.iter().any(|(half_move, _)| m.half_moves.get(0) == Some(half_move)));
+            }
+        }
+        results
+    }
+}
+
+/// Both boards store the position from the active player's perspective with the same point indices,
+/// so the engine can search positions of the bitboard [`crate::backgammon::Board`].
+impl From<crate::backgammon::Board> for Board {
+    fn from(board: crate::backgammon::Board) -> Self {
+        Board {
+            board: std::array::from_fn(|index| board.get_checkers_on_position(index as u8)),
+            active_bar: board.get_active_bar(),
+            inactive_bar: board.get_passive_bar(),
+            active_home: board.get_active_home(),
+            inactive_home: board.get_passive_home(),
+            active_player: board.active_player().into(),
+        }
+    }
+}
+
+/// The reverse of the conversion above, so positions searched by the engine can be played with
+/// the faster move generation. Invalid positions are copied as they are, see
+/// [`crate::backgammon::Board::validate`].
+impl From<Board> for crate::backgammon::Board {
+    fn from(board: Board) -> Self {
+        let mut converted = crate::backgammon::Board::empty();
+        for (index, &checkers) in board.board.iter().enumerate() {
+            converted.set_checkers_on_position(index as u8, checkers);
+        }
+        converted.set_active_bar(board.active_bar);
+        converted.set_passive_bar(board.inactive_bar);
+        converted.set_active_home(board.active_home);
+        converted.set_passive_home(board.inactive_home);
+        converted.set_active_player(board.active_player.into());
+        converted
+    }
+}
+
+impl From<crate::backgammon::Move> for Move {
+    fn from(m: crate::backgammon::Move) -> Self {
+        let position = |position: &crate::backgammon::PositionCompressed| match crate::backgammon::Position::from(*position) {
+            crate::backgammon::Position::Bar => Position::from_enum(PositionEnum::Bar),
+            crate::backgammon::Position::Home => Position::from_enum(PositionEnum::Home),
+            crate::backgammon::Position::Board(index) => Position::from_enum(PositionEnum::Board(index)),
+        };
+        let mut converted = Move::new();
+        for half_move in m.iter() {
+            converted.append(HalfMoveEnum { from: position(half_move.from()), to: position(half_move.to()) });
+        }
+        converted
+    }
+}
+
+impl From<Move> for crate::backgammon::Move {
+    fn from(m: Move) -> Self {
+        let position = |position: Position| match position.to_enum() {
+            PositionEnum::Bar => crate::backgammon::Position::Bar,
+            PositionEnum::Home => crate::backgammon::Position::Home,
+            PositionEnum::Board(index) => crate::backgammon::Position::Board(index),
+        };
+        let mut converted = crate::backgammon::Move::new();
+        for half_move in m.get_half_moves() {
+            converted.add_half_move(crate::backgammon::HalfMove::from_position(position(half_move.from), position(half_move.to)));
+        }
+        converted
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PositionEnum {
     Home,
@@ -403,9 +748,13 @@ pub struct Move {
 
 impl Move {
     pub fn unordered_equal(&self, other: &Move) -> bool {
+        if self.half_moves.len() != other.half_moves.len() {
+            return false;
+        }
+        // Each half move of `other` may only be matched once, `used` marks the matched slots.
         let mut used: u8 = 0;
         for half_move in self.half_moves.iter() {
-            match other.half_moves.iter().enumerate().position(|(i,&hm)| hm == *half_move && !used & (1 << i) != 0) {
+            match other.half_moves.iter().enumerate().position(|(i,&hm)| hm == *half_move && used & (1 << i) == 0) {
                 Some(index) => used |= 1 << index,
                 None => return false,
             }       
@@ -413,6 +762,17 @@ impl Move {
         true 
     }
 
+    /// Key that is equal for two moves exactly if they are [`unordered_equal`](Self::unordered_equal)
+    /// and have the same length, used to deduplicate moves in a hash set.
+    pub(crate) fn unordered_key(&self) -> u64 {
+        let mut codes = [0u16; 4];
+        for (code, half_move) in codes.iter_mut().zip(self.half_moves.iter()) {
+            *code = (half_move.from.position.get() as u16) << 8 | half_move.to.position.get() as u16;
+        }
+        codes.sort_unstable();
+        codes.iter().fold(0, |key, &code| key << 16 | code as u64)
+    }
+
     pub fn new() -> Self {
         Move { half_moves: TinyVector::new() }
     }
@@ -490,8 +850,14 @@ impl Dice {
     }
 
     pub fn roll() -> Dice {
-        let a = random_range(1..=6);
-        let b = random_range(1..=6);
+        Self::roll_with(&mut rand::rng())
+    }
+
+    /// Rolls the dice with the given random number generator, e.g. a seeded `StdRng`
+    /// to reproduce a sequence of rolls.
+    pub fn roll_with<R: Rng>(rng: &mut R) -> Dice {
+        let a = rng.random_range(1..=6);
+        let b = rng.random_range(1..=6);
         Dice::new(a, b)
     }
 
@@ -678,4 +1044,320 @@ pub enum DiceUsage {
     OnlyFirstAvailable,
     OnlySecondAvailable,
     BothUsed,
-}
\ No newline at end of file
+}
+
+/// Error returned by [`play_game`] when a self-play game cannot be finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfPlayError {
+    /// Neither player can move with any roll, see [`Board::is_stuck`].
+    Stuck,
+    /// The game did not end within the given number of turns.
+    TurnLimitExceeded(usize),
+}
+
+impl std::fmt::Display for SelfPlayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelfPlayError::Stuck => write!(f, "neither player can move"),
+            SelfPlayError::TurnLimitExceeded(max_turns) => write!(f, "game did not end within {} turns", max_turns),
+        }
+    }
+}
+
+impl std::error::Error for SelfPlayError {}
+
+/// Plays a game from the starting position, choosing every move with `choose_move`.
+/// Gives up after `max_turns` turns, so a faulty engine or corrupt position cannot loop forever.
+pub fn play_game<R: Rng>(mut choose_move: impl FnMut(&Board, Dice) -> Move, max_turns: usize, rng: &mut R) -> Result<GameOutcome, SelfPlayError> {
+    let mut board = Board::new();
+    for _ in 0..max_turns {
+        if board.outcome() != GameOutcome::Ongoing {
+            break;
+        }
+        let dice = Dice::roll_with(rng);
+        if board.is_stuck(dice) && Dice::ALL.iter().all(|&dice| board.is_stuck(dice)) {
+            return Err(SelfPlayError::Stuck);
+        }
+        board.make_move_unchecked(choose_move(&board, dice));
+    }
+    match board.outcome() {
+        GameOutcome::Ongoing => Err(SelfPlayError::TurnLimitExceeded(max_turns)),
+        outcome => Ok(outcome),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unordered_equal_with_duplicate_half_moves() {
+        let half_move = |from: u8, to: u8| HalfMoveEnum {
+            from: Position::from_enum(PositionEnum::Board(from)),
+            to: Position::from_enum(PositionEnum::Board(to)),
+        };
+        let moves = |half_moves: &[(u8, u8)]| {
+            let mut m = Move::new();
+            for &(from, to) in half_moves {
+                m.append(half_move(from, to));
+            }
+            m
+        };
+        assert!(moves(&[(18, 19), (18, 19)]).unordered_equal(&moves(&[(18, 19), (18, 19)])));
+        assert!(moves(&[(16, 19), (18, 19)]).unordered_equal(&moves(&[(18, 19), (16, 19)])));
+        assert!(!moves(&[(18, 19), (18, 19)]).unordered_equal(&moves(&[(18, 19), (16, 19)])));
+        assert!(!moves(&[(18, 19)]).unordered_equal(&moves(&[(18, 19), (18, 19)])));
+    }
+
+    #[test]
+    fn test_generate_moves_dedup_matches_unordered_equal() {
+        // Counts produced by the previous quadratic `unordered_equal` deduplication.
+        for ((a, b), expected) in [((6, 6), 11), ((1, 1), 42), ((3, 1), 19), ((6, 5), 8)] {
+            let moves = Board::new().generate_moves(Dice::new(a, b));
+            assert_eq!(moves.len(), expected);
+            for (i, m1) in moves.iter().enumerate() {
+                assert!(moves[i + 1..].iter().all(|m2| !m1.unordered_equal(m2)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_eval_matches_default_params() {
+        let mut board = Board::bench();
+        assert_eq!(board.eval(), board.eval_with(&EvalParams::default()));
+        board.switch_player();
+        assert_eq!(board.eval(), board.eval_with(&EvalParams::default()));
+        assert_eq!(Board::new().eval(), Board::new().eval_with(&EvalParams::default()));
+    }
+
+    #[test]
+    fn test_is_race() {
+        assert!(!Board::new().is_race());
+        let board = Board {
+            board: [0,0,-5,0,0,0,0,0,0,0,0,0,0,0,0,-1,0,0,0,3,0,0,4,0],
+            active_bar: 0,
+            inactive_bar: 0,
+            active_home: 8,
+            inactive_home: 9,
+            active_player: Player::White,
+        };
+        assert!(board.is_race());
+    }
+
+    #[test]
+    fn test_race_win_probability() {
+        assert_eq!(Board::new().checked_race_win_probability(), None);
+
+        // 100 pips each, the active player only gains the on-roll advantage.
+        let mut points = [0; 24];
+        points[17] = 10;
+        points[18] = 5;
+        points[6] = -10;
+        points[5] = -5;
+        let equal = Board::from_parts(points, (0, 0), (0, 0), Player::White);
+        let p = equal.checked_race_win_probability().unwrap();
+        assert!((p - 0.5).abs() < 0.1, "{p}");
+
+        points[6] = 0;
+        points[9] = -10;
+        let leading = Board::from_parts(points, (0, 0), (0, 0), Player::White);
+        assert!(leading.race_win_probability() > 0.9);
+
+        let mut trailing = leading;
+        trailing.switch_player();
+        assert!(trailing.race_win_probability() < 0.2);
+    }
+
+    #[test]
+    fn test_pip_count() {
+        let mut board = Board::new();
+        assert_eq!(board.pip_count(Player::White), 167);
+        assert_eq!(board.pip_count(Player::Black), 167);
+        board.active_bar = 1;
+        board.board[0] = 1;
+        assert_eq!(board.pip_count(Player::White), 167 + 1);
+        board.switch_player();
+        assert_eq!(board.pip_count(Player::White), 168);
+    }
+
+    #[test]
+    fn test_pip_difference() {
+        let mut board = Board::new();
+        assert_eq!(board.pip_difference(), 0);
+        board.switch_player();
+        assert_eq!(board.pip_difference(), 0);
+
+        // White has 15 checkers on its 1 point, Black 15 on its 6 point.
+        let mut points = [0; 24];
+        points[23] = 15;
+        points[5] = -15;
+        let mut race = Board::from_parts(points, (0, 0), (0, 0), Player::White);
+        assert_eq!(race.pip_difference(), 15 - 90);
+        race.switch_player();
+        assert_eq!(race.pip_difference(), 90 - 15);
+    }
+
+    #[test]
+    fn test_eval_normalized_preserves_order() {
+        let mut gammon = Board::from_parts([0; 24], (0, 0), (15, 0), Player::White);
+        gammon.board[0] = -15;
+        let mut win = gammon;
+        win.inactive_home = 1;
+        win.board[0] = -14;
+        let mut backgammon = gammon;
+        backgammon.inactive_bar = 1;
+        backgammon.board[0] = -14;
+        let mut lost = win;
+        lost.switch_player();
+
+        let mut behind = Board::bench();
+        behind.switch_player();
+        let positions = [lost, behind, Board::new(), Board::bench(), win, gammon, backgammon];
+        let raw: Vec<f32> = positions.iter().map(Board::eval).collect();
+        let normalized: Vec<f32> = positions.iter().map(Board::eval_normalized).collect();
+        assert!(raw.is_sorted_by(|a, b| a < b), "{raw:?}");
+        assert!(normalized.is_sorted_by(|a, b| a < b), "{normalized:?}");
+
+        assert_eq!(normalized[0], -1.0);
+        assert!(normalized[1..4].iter().all(|e| e.abs() < 1.0));
+        assert_eq!(&normalized[4..], &[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_outcome_points() {
+        let mut board = Board::from_parts([0; 24], (0, 0), (15, 0), Player::Black);
+        board.board[0] = -15;
+        assert_eq!(board.outcome(), GameOutcome::Gammon(Player::Black));
+        assert_eq!(board.outcome().winner(), Some(Player::Black));
+        assert_eq!(board.outcome().points_with_cube(2), 4);
+        assert_eq!(Board::new().outcome().winner(), None);
+        assert_eq!(Board::new().outcome().base_points(), 0);
+    }
+
+    #[test]
+    fn test_eval_with_bar_penalty() {
+        let board = Board::bench();
+        let params = EvalParams { bar_penalty: 10, ..EvalParams::default() };
+        assert_eq!(board.eval_with(&params), board.eval() - 10.0);
+    }
+
+    #[test]
+    fn test_eval_closeout() {
+        let board = |open_point: bool, trapped: u8| {
+            let mut points = [0; 24];
+            points[12] = 15 - trapped as i8;
+            points[..6].fill(-2);
+            points[6] = -3;
+            if open_point {
+                points[0] = -1;
+                points[6] = -4;
+            }
+            Board::from_parts(points, (trapped, 0), (0, 0), Player::White)
+        };
+        let (closed, near) = (board(false, 1), board(true, 1));
+        assert_eq!(closed.closed_out_checkers(Player::White), 1);
+        assert_eq!(near.closed_out_checkers(Player::White), 0);
+        assert_eq!(closed.closed_out_checkers(Player::Black), 0);
+        assert!(closed.eval() < near.eval() - 40.0);
+        // The penalty grows with the number of trapped checkers.
+        assert!(board(false, 3).eval() < board(true, 3).eval() - 3.0 * 40.0);
+    }
+
+    #[test]
+    fn test_self_play_never_gets_stuck() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut move_rng = StdRng::seed_from_u64(12);
+        for _ in 0..20 {
+            let choose_move = |board: &Board, dice: Dice| *board.generate_moves(dice).choose(&mut move_rng).unwrap();
+            let outcome = play_game(choose_move, 10_000, &mut rng).unwrap();
+            assert_ne!(outcome, GameOutcome::Ongoing);
+        }
+        assert_eq!(play_game(|board, dice| board.generate_moves(dice)[0], 0, &mut rng), Err(SelfPlayError::TurnLimitExceeded(0)));
+    }
+
+    #[test]
+    fn test_is_stuck() {
+        assert!(Dice::ALL.iter().all(|&dice| !Board::new().is_stuck(dice)));
+
+        // Both players have a checker on the bar against a closed board.
+        let mut points = [0; 24];
+        points[18..24].copy_from_slice(&[2; 6]);
+        points[0..6].copy_from_slice(&[-2; 6]);
+        points[10] = 2;
+        points[13] = -2;
+        let board = Board::from_parts(points, (1, 1), (0, 0), Player::White);
+        assert!(Dice::ALL.iter().all(|&dice| board.is_stuck(dice)));
+    }
+
+    #[test]
+    fn test_greedy_move_prefers_hit() {
+        // White can hit the Black blot on index 10 from its point on index 7 with the 3,
+        // or play safely inside its home board. A point is left open, so the hit is no closeout.
+        let mut points = [0; 24];
+        points[18..24].copy_from_slice(&[5, 0, 2, 2, 2, 2]);
+        points[7] = 2;
+        points[10] = -1;
+        points[0..5].copy_from_slice(&[-3, -3, -3, -3, -2]);
+        let board = Board::from_parts(points, (0, 0), (0, 0), Player::White);
+        let dice = Dice::new(3, 1);
+
+        let equity = |m: &Move| {
+            let mut after = board;
+            after.make_move_unchecked(*m);
+            -after.eval_normalized()
+        };
+        let greedy = board.greedy_move(dice);
+        assert!(board.generate_moves(dice).contains(&greedy));
+        assert!(board.captured_value(&greedy) > 0);
+        let best_quiet = board.generate_moves(dice).into_iter()
+            .filter(|m| board.captured_value(m) == 0)
+            .max_by(|a, b| equity(a).total_cmp(&equity(b)))
+            .unwrap();
+        assert!((equity(&greedy) - equity(&best_quiet)).abs() < 0.1);
+        assert!(board.quick_score(&greedy, dice) > board.quick_score(&best_quiet, dice));
+    }
+
+    #[test]
+    fn test_legal_move_fingerprint() {
+        let board = Board::new();
+        let dice = Dice::new(3, 1);
+        assert_eq!(board.legal_move_fingerprint(dice), Board::new().legal_move_fingerprint(dice));
+        assert_ne!(board.legal_move_fingerprint(dice), board.legal_move_fingerprint(Dice::new(4, 2)));
+        let mut after = board;
+        after.make_move_unchecked(board.generate_moves(dice)[0]);
+        assert_ne!(after.legal_move_fingerprint(dice), board.legal_move_fingerprint(dice));
+    }
+
+    #[test]
+    fn test_board_conversion_round_trip() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut bench_black = Board::bench();
+        bench_black.switch_player();
+        let mut positions = vec![Board::new(), Board::bench(), bench_black];
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut board = crate::backgammon::Board::new();
+        while board.outcome() == crate::backgammon::GameOutcome::Ongoing {
+            positions.push(board.into());
+            let moves = board.generate_moves(crate::backgammon::Dice::roll_with(&mut rng));
+            board.make_move_unchecked(*moves.choose(&mut rng).unwrap());
+        }
+
+        for legacy in positions {
+            let converted = crate::backgammon::Board::from(legacy);
+            assert_eq!(converted.validate(), Ok(()));
+            assert_eq!(Board::from(converted), legacy);
+            assert_eq!(crate::backgammon::Board::from(Board::from(converted)), converted);
+            assert_eq!(Player::from(converted.active_player()), legacy.active_player());
+            let whites_view = converted.from_whites_perspective();
+            for index in 0..24 {
+                assert_eq!(whites_view.get_checkers_on_position(index), legacy.checkers_on_position(index));
+            }
+            for player in [Player::White, Player::Black] {
+                assert_eq!(converted.borne_off(player.into()), legacy.home(player));
+            }
+        }
+    }
+}
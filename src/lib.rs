@@ -1,4 +1,17 @@
+//! Without the default `std` feature the crate is `no_std` and allocation free. Only the core of
+//! [`backgammon`] remains: [`backgammon::Board`] with [`backgammon::Board::generate_half_moves`] and
+//! [`backgammon::Board::make_move_unchecked`], [`backgammon::Dice`], [`backgammon::Move`] and the
+//! position types. The `Vec` based move generators, notation, the engine and the GUI need `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
 pub mod game;
+#[cfg(feature = "std")]
 pub mod engine;
 pub mod backgammon;
-pub mod misc;
\ No newline at end of file
+pub mod misc;
+#[cfg(feature = "cdylib")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -31,7 +31,7 @@ fn main() {
     // println!("TinyVector test completed in {:?}", end.duration_since(start));
     // println!("Sum: {}", s);
    
-    // run_games();
+    // run_games(10_000).unwrap();
     // benchmark();
 
     let depth = 2;
@@ -144,7 +144,7 @@ fn update(app: &nannou::App, model: &mut Model, update: nannou::event::Update) {
                         //     Player::Black => choose_random_move(&board, dice),
                         // };
                         // best_move
-                        monte_carlo_search(&board, dice, 1000, 20)
+                        monte_carlo_search(&board, dice, 1000, 20, &mut rng())
                     }));
                 },
                 Some(thread) => {
@@ -538,6 +538,18 @@ fn view(app: &nannou::App, model: &Model, frame: nannou::frame::Frame) {
 
     y -= 30.0;
 
+    let pips = format!("Pips: White {} | Black {} ({:+} for the player to move)",
+        model.board.pip_count(Player::White),
+        model.board.pip_count(Player::Black),
+        model.board.pip_difference());
+    draw.text(&pips)
+        .x_y(x, y)
+        .w(stats_rect_width - 20.0)
+        .font_size(16)
+        .color(nannou::color::WHITE);
+
+    y -= 30.0;
+
     let total_wins_white = model.wins.0 + model.gammons.0 + model.backgammons.0;
     let total_wins_black = model.wins.1 + model.gammons.1 + model.backgammons.1;
     let winrate_white = if model.games_played > 0 {
@@ -627,7 +639,8 @@ fn view(app: &nannou::App, model: &Model, frame: nannou::frame::Frame) {
 }
 
 
-fn run_games() {
+/// Plays engine games forever, aborting with an error if a game does not end within `max_turns`.
+fn run_games(max_turns: usize) -> Result<(), game::SelfPlayError> {
     let mut games = 0;
     let mut white_wins = 0;
     let mut white_gammon = 0;
@@ -636,55 +649,19 @@ fn run_games() {
     let mut black_gammon = 0;
     let mut black_backgammon = 0;
     loop {
-        let mut board = Board::new();
         games += 1;
-        loop {
-            // print!("=========================================================\n");
-            // println!("{}", board.to_fancy_string());
-            // print!("Curent Evaluation: {}\n", board.eval_absolute());
-            // println!("White Home: {}, White Bar: {}, Black Home: {}, Black Bar: {}", board.home(Player::White), board.bar(Player::White), board.home(Player::Black), board.bar(Player::Black));              
-            match board.outcome() {
-                GameOutcome::Ongoing => {
-                    let dice = Dice::roll();
-                    
-                    // println!("{:?} rolled {}", board.get_active_player(), dice.to_string());
-
-                    // let start = std::time::Instant::now();
-                    let mv = match board.get_active_player() {
-                        Player::White => monte_carlo_search(&board, dice, 1000, 20),
-                        Player::Black => mcts_search(board, dice, 100000),
-                    };
-                    // let duration = start.elapsed();
-                    // println!("{:?} moved {}", board.get_active_player(), mv.to_string());   
-                    // println!("Move evaluation took: {:?}", duration);
-                    
-                    board.make_move_unchecked(mv);
-                }
-                GameOutcome::Win(player) => {
-                    // println!("Player {:?} wins!", player);
-                    match player {
-                        Player::White => white_wins += 1,
-                        Player::Black => black_wins += 1,
-                    }
-                    break;
-                }
-                GameOutcome::Gammon(player) => {
-                    // println!("Player {:?} wins with a gammon!", player);
-                    match player {
-                        Player::White => white_gammon += 1,
-                        Player::Black => black_gammon += 1,
-                    }
-                    break;
-                }
-                GameOutcome::Backgammon(player) => {
-                    // println!("Player {:?} wins with a backgammon!", player);
-                    match player {
-                        Player::White => white_backgammon += 1,
-                        Player::Black => black_backgammon += 1,
-                    }
-                    break;
-                }
-            }
+        let choose_move = |board: &Board, dice: Dice| match board.get_active_player() {
+            Player::White => monte_carlo_search(board, dice, 1000, 20, &mut rng()),
+            Player::Black => mcts_search(*board, dice, 100000, &mut rng()),
+        };
+        match game::play_game(choose_move, max_turns, &mut rng())? {
+            GameOutcome::Win(Player::White) => white_wins += 1,
+            GameOutcome::Win(Player::Black) => black_wins += 1,
+            GameOutcome::Gammon(Player::White) => white_gammon += 1,
+            GameOutcome::Gammon(Player::Black) => black_gammon += 1,
+            GameOutcome::Backgammon(Player::White) => white_backgammon += 1,
+            GameOutcome::Backgammon(Player::Black) => black_backgammon += 1,
+            GameOutcome::Ongoing => unreachable!("play_game only returns finished games"),
         }
         let white_win_rate = (white_wins + white_gammon + white_backgammon) as f32 / games as f32 * 100.0;
         let black_win_rate = (black_wins + black_gammon + black_backgammon) as f32 / games as f32 * 100.0;
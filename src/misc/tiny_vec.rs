@@ -1,4 +1,4 @@
-use std::{mem::MaybeUninit, ops::Deref};
+use core::{mem::MaybeUninit, ops::{Deref, DerefMut}};
 
 #[derive(Debug, Clone, Copy)]
 pub struct TinyVec<T, const N: usize> 
@@ -43,9 +43,77 @@ where
         } else { None }
     }
     
+    /// Removes the element at `idx`, shifting all following elements down.
+    /// Returns `None` if `idx` is out of bounds.
+    pub fn remove(&mut self, idx: usize) -> Option<T> {
+        if idx >= self.len as usize {
+            return None;
+        }
+        // SAFETY: idx < len, so the slot was initialized
+        let val = unsafe { self.buf[idx].assume_init_read() };
+        self.buf.copy_within(idx + 1..self.len as usize, idx);
+        self.len -= 1;
+        Some(val)
+    }
+
+    /// Removes the first element equal to `val`. Returns whether an element was removed.
+    pub fn remove_value(&mut self, val: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        match self.iter().position(|v| v == val) {
+            Some(idx) => self.remove(idx).is_some(),
+            None => false,
+        }
+    }
+
+    /// Inserts `val` at `idx`, shifting all following elements up.
+    ///
+    /// # Panics
+    /// Panics if `idx > len` or if the vector is full.
+    pub fn insert(&mut self, idx: usize, val: T) {
+        assert!(idx <= self.len as usize, "insertion index {} out of bounds (len {})", idx, self.len);
+        assert!((self.len as usize) < N, "TinyVec is full");
+        self.buf.copy_within(idx..self.len as usize, idx + 1);
+        self.buf[idx].write(val);
+        self.len += 1;
+    }
+
+    pub fn contains(&self, val: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|v| v == val)
+    }
+
     pub fn len(&self) -> u8 {
         self.len
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all elements. `T` is `Copy`, so nothing needs to be dropped.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|idx| self.get(idx as usize))
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.deref().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, T> {
+        self.deref_mut().iter_mut()
+    }
 }
 
 impl<T: Copy, const N: usize> Deref for TinyVec<T, N> {
@@ -54,7 +122,175 @@ impl<T: Copy, const N: usize> Deref for TinyVec<T, N> {
     fn deref(&self) -> &Self::Target {
         // SAFETY: Only the first `self.len` elements are initialized
         unsafe {
-            std::slice::from_raw_parts(self.buf.as_ptr() as *const T, self.len as usize)
+            core::slice::from_raw_parts(self.buf.as_ptr() as *const T, self.len as usize)
+        }
+    }
+}
+
+impl<T: Copy, const N: usize> DerefMut for TinyVec<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: Only the first `self.len` elements are initialized
+        unsafe {
+            core::slice::from_raw_parts_mut(self.buf.as_mut_ptr() as *mut T, self.len as usize)
+        }
+    }
+}
+
+/// By-value iterator over the initialized elements of a [`TinyVec`].
+#[derive(Debug, Clone, Copy)]
+pub struct IntoIter<T, const N: usize>
+where
+    T: Copy,
+{
+    vec: TinyVec<T, N>,
+    index: u8,
+}
+
+impl<T: Copy, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.vec.len {
+            return None;
         }
+        // SAFETY: index < len, so the slot was initialized
+        let val = unsafe { self.vec.buf[self.index as usize].assume_init_read() };
+        self.index += 1;
+        Some(val)
     }
-}
\ No newline at end of file
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.vec.len - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Copy, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T: Copy, const N: usize> IntoIterator for TinyVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { vec: self, index: 0 }
+    }
+}
+
+impl<'a, T: Copy, const N: usize> IntoIterator for &'a TinyVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Copy, const N: usize> IntoIterator for &'a mut TinyVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = core::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiny_vec(values: &[u32]) -> TinyVec<u32, 8> {
+        let mut vec = TinyVec::new();
+        for &v in values {
+            vec.push(v);
+        }
+        vec
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut vec = tiny_vec(&[1, 2, 3]);
+        vec.clear();
+        assert_eq!(vec.len(), 0);
+        assert!(vec.iter().next().is_none());
+        vec.push(4);
+        assert_eq!(&*vec, &[4]);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(TinyVec::<u32, 8>::new().is_empty());
+        let mut vec = tiny_vec(&[1]);
+        assert!(!vec.is_empty());
+        vec.pop();
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_first_and_last() {
+        let vec = tiny_vec(&[1, 2, 3]);
+        assert_eq!(vec.first(), Some(&1));
+        assert_eq!(vec.last(), Some(&3));
+        assert_eq!(tiny_vec(&[5]).first(), tiny_vec(&[5]).last());
+        assert_eq!(TinyVec::<u32, 8>::new().first(), None);
+        assert_eq!(TinyVec::<u32, 8>::new().last(), None);
+    }
+
+    #[test]
+    fn test_into_iter_collects_only_initialized() {
+        let vec = tiny_vec(&[1, 2, 3]);
+        assert_eq!(vec.into_iter().len(), 3);
+        assert_eq!(vec.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(TinyVec::<u32, 8>::new().into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut vec = tiny_vec(&[1, 2, 3]);
+        for v in vec.iter_mut() {
+            *v *= 10;
+        }
+        for v in &mut vec {
+            *v += 1;
+        }
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![11, 21, 31]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut vec = tiny_vec(&[1, 2, 3, 4]);
+        assert_eq!(vec.remove(1), Some(2));
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4]);
+        assert_eq!(vec.remove(2), Some(4));
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(vec.remove(2), None);
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_value_and_contains() {
+        let mut vec = tiny_vec(&[1, 2, 3, 2]);
+        assert!(vec.contains(&2));
+        assert!(vec.remove_value(&2));
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![1, 3, 2]);
+        assert!(vec.remove_value(&2));
+        assert!(!vec.contains(&2));
+        assert!(!vec.remove_value(&2));
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut vec = tiny_vec(&[1, 3]);
+        vec.insert(1, 2);
+        vec.insert(3, 4);
+        vec.insert(0, 0);
+        assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_full() {
+        let mut vec = tiny_vec(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        vec.insert(0, 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! WebAssembly bindings for browser frontends, enabled with the `wasm` feature.
+//! The module is built with `cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`.
+//!
+//! Moves cross the boundary in standard notation (see [`Move`]), move lists as a
+//! JSON array of notation strings.
+
+use wasm_bindgen::prelude::*;
+
+use crate::backgammon::{Board, Dice, Move};
+use crate::engine;
+
+/// A position with the active player to move.
+#[wasm_bindgen]
+pub struct WasmBoard {
+    board: Board,
+}
+
+#[wasm_bindgen]
+impl WasmBoard {
+    /// Creates the starting position with White to move.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmBoard {
+        WasmBoard { board: Board::new() }
+    }
+
+    /// Legal moves for the roll as a JSON array of notation strings, e.g. `["24/21 24/23", ...]`.
+    /// If no checker can be moved the array holds a single empty string.
+    pub fn generate_moves(&self, die1: u8, die2: u8) -> Result<String, String> {
+        let dice = dice(die1, die2)?;
+        let notations = self.board.generate_moves(dice).iter()
+            .map(|m| format!("\"{}\"", m.to_notation(&self.board)))
+            .collect::<Vec<_>>();
+        Ok(format!("[{}]", notations.join(",")))
+    }
+
+    /// Best move for the roll found by the engine searching `depth` plies, in notation.
+    pub fn find_best_move(&self, die1: u8, die2: u8, depth: u8) -> Result<String, String> {
+        let best = engine::find_best_legal_move(&self.board, dice(die1, die2)?, depth);
+        Ok(best.to_notation(&self.board))
+    }
+
+    /// Plays the move given in notation and passes the turn to the opponent.
+    pub fn apply_move(&mut self, notation: &str, die1: u8, die2: u8) -> Result<(), String> {
+        let dice = dice(die1, die2)?;
+        let m = notation.parse::<Move>().map_err(|e| e.to_string())?;
+        self.board.make_move(m, dice).map_err(|e| e.to_string())
+    }
+
+    /// True if White is to move.
+    pub fn white_to_move(&self) -> bool {
+        self.board.active_player() == crate::backgammon::Player::White
+    }
+}
+
+impl Default for WasmBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rolls two dice, returned as `[die1, die2]`.
+#[wasm_bindgen]
+pub fn roll_dice() -> Vec<u8> {
+    let dice = Dice::roll();
+    vec![dice.die1(), dice.die2()]
+}
+
+fn dice(die1: u8, die2: u8) -> Result<Dice, String> {
+    if !(1..=6).contains(&die1) || !(1..=6).contains(&die2) {
+        return Err(format!("invalid roll {}-{}", die1, die2));
+    }
+    Ok(Dice::from_numbers(die1, die2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_moves_json_parses() {
+        let board = WasmBoard::new();
+        let json = board.generate_moves(3, 1).unwrap();
+        let notations: Vec<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(notations.len(), Board::new().generate_moves(Dice::from_numbers(3, 1)).len());
+        for notation in &notations {
+            assert!(notation.parse::<Move>().is_ok(), "{}", notation);
+        }
+        assert!(notations.iter().any(|n| n == "6/5 8/5"));
+    }
+
+    #[test]
+    fn test_best_move_can_be_applied() {
+        let mut board = WasmBoard::new();
+        let best = board.find_best_move(6, 5, 1).unwrap();
+        board.apply_move(&best, 6, 5).unwrap();
+        assert!(!board.white_to_move());
+        assert!(board.generate_moves(0, 3).is_err());
+        let dice = roll_dice();
+        assert!(dice.iter().all(|die| (1..=6).contains(die)));
+    }
+
+    #[test]
+    fn test_best_move_can_be_applied_in_random_games() {
+        use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..20 {
+            let mut board = WasmBoard::new();
+            while board.board.outcome() == crate::backgammon::GameOutcome::Ongoing {
+                let dice = Dice::roll_with(&mut rng);
+                let (die1, die2) = (dice.die1(), dice.die2());
+                let best = board.find_best_move(die1, die2, 0).unwrap();
+                let mut expected = WasmBoard { board: board.board };
+                assert_eq!(expected.apply_move(&best, die1, die2), Ok(()), "{} with {}", best, dice);
+
+                // Continue with a random move to reach positions the engine would avoid.
+                let m = *board.board.generate_moves(dice).choose(&mut rng).unwrap();
+                board.apply_move(&m.to_notation(&board.board), die1, die2).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell};
+
+use backgammon::game::{Board, Dice, MoveGenerator};
+
+/// Counts the allocations of the current thread, so tests running in parallel do not interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_move_generator_reuses_buffers() {
+    let board = Board::new();
+    let dice = Dice::new(2, 2);
+    let mut generator = MoveGenerator::new();
+    assert_eq!(generator.generate_moves(&board, dice), board.generate_moves(dice).as_slice());
+
+    let reused = count_allocations(|| {
+        for _ in 0..100 {
+            generator.generate_moves(&board, dice);
+        }
+    });
+    let fresh = count_allocations(|| {
+        for _ in 0..100 {
+            MoveGenerator::new().generate_moves(&board, dice);
+        }
+    });
+    assert_eq!(reused, 0);
+    assert!(fresh >= 100);
+}
@@ -0,0 +1,45 @@
+use std::{io::Write, process::{Command, Stdio}};
+
+fn run_cli(script: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to start the cli");
+    child.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_scripted_session() {
+    let output = run_cli("\
+moves
+dice 3/1
+moves
+play 8/4
+play 8/5 6/5
+undo
+hint
+play 8/5 6/5
+board
+eval
+frobnicate
+quit
+roll
+");
+    let lines = output.lines().collect::<Vec<_>>();
+    assert!(lines.contains(&"roll the dice first"));
+    assert!(lines.contains(&"White rolled 3/1"));
+    assert!(lines.iter().any(|line| line.ends_with(". 6/5 8/5")));
+    assert!(lines.iter().any(|line| line.starts_with("illegal move")));
+    assert!(lines.contains(&"took back the last move"));
+    assert!(lines.iter().any(|line| line.starts_with("best move: ")));
+    assert_eq!(lines.iter().filter(|&&line| line == "White played 8/5 6/5").count(), 2);
+    assert!(lines.contains(&"Black to move, dice: not rolled"));
+    assert!(lines.iter().any(|line| line.starts_with("eval for Black: ")));
+    assert!(lines.iter().any(|line| line.starts_with("unknown command frobnicate")));
+    // Commands after quit are not executed.
+    assert!(!lines.iter().any(|line| line.starts_with("Black rolled")));
+}
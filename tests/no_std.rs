@@ -0,0 +1,15 @@
+use std::{path::Path, process::Command};
+
+/// Builds the library without default features, which compiles the core of `backgammon` with `#![no_std]`.
+#[test]
+fn test_core_builds_without_std() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--no-default-features"])
+        .current_dir(manifest_dir)
+        // A separate target directory avoids waiting for the lock held by the running `cargo test`.
+        .env("CARGO_TARGET_DIR", manifest_dir.join("target").join("no_std"))
+        .output()
+        .expect("Failed to run cargo");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}